@@ -0,0 +1,38 @@
+//! Fault-injection helpers for `Config::chaos`, letting staging/integration
+//! tests exercise client reconnect, resync, and coalescing logic against a
+//! deliberately flaky connection instead of only a perfectly reliable one.
+//! Every knob defaults to off, so `ChaosConfig::default()` (the default
+//! when nothing is configured) never changes behavior in production.
+
+use rand::Rng;
+
+use crate::config::ChaosConfig;
+
+fn roll(probability: f64) -> bool {
+    probability > 0.0 && rand::thread_rng().gen_bool(probability.clamp(0.0, 1.0))
+}
+
+/// Whether an outbound message to this connection should be silently
+/// dropped right now. From the client's point of view this looks exactly
+/// like a dropped broadcast — the message never arrives — even though the
+/// broadcast channel itself delivered it normally.
+pub fn should_drop_broadcast(chaos: &ChaosConfig) -> bool {
+    roll(chaos.drop_broadcast_probability)
+}
+
+/// Whether this connection should be forcibly disconnected right now, as
+/// if it had gone idle, to exercise reconnect logic on an otherwise
+/// healthy connection.
+pub fn should_force_disconnect(chaos: &ChaosConfig) -> bool {
+    roll(chaos.forced_disconnect_probability)
+}
+
+/// Sleeps for `ChaosConfig::extra_latency_ms` if set, simulating a slow
+/// link before a send.
+pub async fn inject_latency(chaos: &ChaosConfig) {
+    if let Some(ms) = chaos.extra_latency_ms {
+        if ms > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(ms)).await;
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tracing::info;
+
+use crate::handler::{participant_idle_ttl, room_idle_ttl};
+use crate::room::PARTICIPANT_IDLE_REMINDER_LEAD;
+use crate::state::AppState;
+use crate::ws::{CloseReason, ServerMessage};
+
+/// How long before expiry we warn still-open tabs, giving them a chance
+/// to export the room before the GC sweep below removes it.
+const EXPIRY_WARNING_LEAD: Duration = Duration::from_secs(10 * 60);
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Background task: periodically warns about, then removes, rooms that
+/// have had no WebSocket activity for `handler::room_idle_ttl` (by default
+/// `room::ROOM_IDLE_TTL`, overridable via `Config::room_idle_ttl_secs`).
+pub async fn run(state: AppState) {
+    let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        sweep(&state).await;
+    }
+}
+
+async fn sweep(state: &AppState) {
+    let ttl = room_idle_ttl(state);
+    let mut rooms = state.rooms.write().await;
+    let mut expired = Vec::new();
+    for (id, room) in rooms.iter_mut() {
+        let awaiting_scheduled_start = room.scheduled_for.is_some_and(|t| t > chrono::Utc::now());
+        if !room.persistent && !awaiting_scheduled_start {
+            let idle = room.idle_for();
+            if idle >= ttl {
+                room.broadcast(ServerMessage::RoomClosing { reason: CloseReason::RoomClosed });
+                expired.push(*id);
+            } else if idle >= ttl.saturating_sub(EXPIRY_WARNING_LEAD) {
+                let expires_at: DateTime<Utc> = Utc::now() + (ttl - idle);
+                room.broadcast(ServerMessage::RoomExpiryWarning { expires_at });
+            }
+        }
+        if let Some(grace_secs) = state.config.owner_failover_grace_secs {
+            if let Some(new_owner_id) = room.maybe_failover_owner(Duration::from_secs(grace_secs)) {
+                info!(room_id = %room.id, %new_owner_id, "owner inactive past grace period, promoted new owner");
+                room.broadcast(ServerMessage::OwnershipTransferred { owner_id: new_owner_id });
+            }
+        }
+        reap_inactive_participants(room, participant_idle_ttl(state));
+    }
+    for id in expired {
+        if let Some(room) = rooms.remove(&id) {
+            info!(room_id = %room.id, "removed idle room");
+            state.notifications.notify(crate::notify::NotificationEvent::SessionEnded { room_id: room.id });
+            if let Some(cluster) = &state.cluster {
+                let _ = cluster.unregister_room(room.id).await;
+            }
+        }
+    }
+}
+
+/// Reminds, then drops, participants who haven't sent anything in a
+/// while. Run on every sweep tick regardless of room idleness, since a
+/// busy room can still have one quiet tab left open in the background.
+fn reap_inactive_participants(room: &mut crate::room::Room, ttl: Duration) {
+    let mut to_remove = Vec::new();
+    for (id, user) in room.users.iter() {
+        let idle = user.last_seen.elapsed();
+        if idle >= ttl {
+            to_remove.push(*id);
+        } else if idle >= ttl.saturating_sub(PARTICIPANT_IDLE_REMINDER_LEAD) {
+            room.broadcast(ServerMessage::InactivityReminder { user_id: *id });
+        }
+    }
+    if to_remove.is_empty() {
+        return;
+    }
+    let before = crate::handler::visible_users(room);
+    for id in &to_remove {
+        room.broadcast(ServerMessage::ForceDisconnect { user_id: *id, reason: CloseReason::IdleTimeout });
+        room.users.remove(id);
+        for story in &mut room.stories {
+            story.votes.remove(id);
+        }
+    }
+    crate::handler::broadcast_users_delta(room, before);
+}
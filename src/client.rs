@@ -0,0 +1,193 @@
+//! Typed async client for the room WebSocket protocol defined in `ws`,
+//! so bots, load tests, and the CLI client share one implementation of
+//! the framing instead of each hand-rolling their own. Gated behind the
+//! `client` Cargo feature since the server binary itself never needs a
+//! WebSocket *client*.
+
+use std::collections::VecDeque;
+
+use futures_util::stream::SplitSink;
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use uuid::Uuid;
+
+use crate::room::Role;
+use crate::ws::{ClientMessage, IncomingMessage, ServerMessage};
+
+type Sink = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, WsMessage>;
+
+/// Errors a [`Client`] can hit connecting to or talking with a room.
+#[derive(Debug)]
+pub enum ClientError {
+    WebSocket(tokio_tungstenite::tungstenite::Error),
+    /// An outgoing message couldn't be encoded as JSON. Incoming frames
+    /// that don't decode as a `ServerMessage` are skipped by the
+    /// background reader rather than reported.
+    Protocol(serde_json::Error),
+    /// The connection closed before the expected reply (e.g. `join`'s own
+    /// `UsersDelta`) ever showed up.
+    Closed(&'static str),
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::WebSocket(err) => write!(f, "websocket error: {err}"),
+            ClientError::Protocol(err) => write!(f, "failed to encode protocol message: {err}"),
+            ClientError::Closed(what) => write!(f, "connection closed before {what} arrived"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<tokio_tungstenite::tungstenite::Error> for ClientError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        Self::WebSocket(err)
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Protocol(err)
+    }
+}
+
+/// A connected room session. Outgoing commands go through the methods
+/// below (or [`Client::send`] for anything not yet wrapped); incoming
+/// `ServerMessage`s are pulled with [`Client::recv`].
+///
+/// Frames are read off the socket by a background task as soon as
+/// `connect` returns, so nothing is missed while a caller is busy
+/// sending — `recv` just drains the buffered queue.
+pub struct Client {
+    sink: Sink,
+    events: mpsc::UnboundedReceiver<ServerMessage>,
+    /// Events consumed by a helper (like `join`) while it was looking for
+    /// something specific, put back here so `recv` still sees them.
+    buffered: VecDeque<ServerMessage>,
+    user_id: Option<Uuid>,
+}
+
+impl Client {
+    /// Opens the WebSocket connection and starts the background reader.
+    /// `url` is the full `ws://`/`wss://` room URL (including the room id
+    /// and any query-string auth the server's `http` layer expects).
+    pub async fn connect(url: &str) -> Result<Self, ClientError> {
+        let (stream, _response) = tokio_tungstenite::connect_async(url).await?;
+        let (sink, mut source) = stream.split();
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(frame) = source.next().await {
+                match frame {
+                    Ok(WsMessage::Text(text)) => {
+                        if let Ok(message) = serde_json::from_str::<ServerMessage>(&text) {
+                            if tx.send(message).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Ok(WsMessage::Close(_)) | Err(_) => break,
+                    Ok(_) => {}
+                }
+            }
+        });
+        Ok(Self { sink, events: rx, buffered: VecDeque::new(), user_id: None })
+    }
+
+    /// The id the server assigned us, once [`Client::join`] has
+    /// completed. `None` beforehand.
+    pub fn user_id(&self) -> Option<Uuid> {
+        self.user_id
+    }
+
+    /// Waits for the next `ServerMessage`, whether newly arrived or
+    /// buffered by an earlier helper call. Returns `None` once the
+    /// connection is closed and no more messages are coming.
+    pub async fn recv(&mut self) -> Option<ServerMessage> {
+        if let Some(message) = self.buffered.pop_front() {
+            return Some(message);
+        }
+        self.events.recv().await
+    }
+
+    /// Sends a raw `ClientMessage` with no correlation id. Prefer the
+    /// named helpers below when one exists; this is the escape hatch for
+    /// everything else the protocol supports.
+    pub async fn send(&mut self, message: ClientMessage) -> Result<(), ClientError> {
+        self.send_with_request_id(message, None).await
+    }
+
+    /// Like [`Client::send`], but tags the frame with `request_id` so the
+    /// caller can correlate the eventual `Ack`/`Nack` in `recv`.
+    pub async fn send_with_request_id(&mut self, message: ClientMessage, request_id: Option<String>) -> Result<(), ClientError> {
+        let text = serde_json::to_string(&IncomingMessage { request_id, message })?;
+        self.sink.send(WsMessage::Text(text)).await?;
+        Ok(())
+    }
+
+    /// Joins the room under `name`/`role` and waits for the server to
+    /// confirm it, returning our own assigned user id.
+    ///
+    /// `Join` itself gets no direct reply (see `handler::handle_client_message`);
+    /// the server only ever tells us who we are indirectly, via the
+    /// `UsersDelta`/`RoomState` broadcast it fans out to the whole room
+    /// afterward. So this looks for the first such broadcast containing a
+    /// user named `name` and takes that as us — any other event seen
+    /// while waiting is kept for a later `recv` rather than dropped.
+    pub async fn join(&mut self, name: impl Into<String>, role: Role) -> Result<Uuid, ClientError> {
+        self.join_with(name, role, None).await
+    }
+
+    /// Like [`Client::join`], but presents `owner_token` (the `owner_id`
+    /// returned once by `POST /api/rooms`) so the connection claims the
+    /// owner seat instead of being assigned a random id.
+    pub async fn join_as_owner(&mut self, name: impl Into<String>, owner_token: Uuid) -> Result<Uuid, ClientError> {
+        self.join_with(name, Role::Owner, Some(owner_token)).await
+    }
+
+    async fn join_with(&mut self, name: impl Into<String>, role: Role, owner_token: Option<Uuid>) -> Result<Uuid, ClientError> {
+        let name = name.into();
+        self.send(ClientMessage::Join { name: name.clone(), role, guest_token: None, is_bot: false, owner_token }).await?;
+        loop {
+            let Some(event) = self.recv().await else { return Err(ClientError::Closed("join confirmation")) };
+            let found = match &event {
+                ServerMessage::UsersDelta { added, .. } => added.iter().find(|u| u.name == name).map(|u| u.id),
+                ServerMessage::RoomState { users, .. } => users.iter().find(|u| u.name == name).map(|u| u.id),
+                _ => None,
+            };
+            self.buffered.push_back(event);
+            if let Some(id) = found {
+                self.user_id = Some(id);
+                return Ok(id);
+            }
+        }
+    }
+
+    pub async fn add_story(&mut self, title: impl Into<String>, description: impl Into<String>) -> Result<(), ClientError> {
+        self.send(ClientMessage::AddStory { title: title.into(), description: description.into() }).await
+    }
+
+    pub async fn select_story(&mut self, story_id: Uuid) -> Result<(), ClientError> {
+        self.send(ClientMessage::SelectStory { story_id }).await
+    }
+
+    pub async fn vote(&mut self, story_id: Uuid, value: impl Into<String>) -> Result<(), ClientError> {
+        self.send(ClientMessage::Vote { story_id, value: value.into() }).await
+    }
+
+    pub async fn reveal(&mut self) -> Result<(), ClientError> {
+        self.send(ClientMessage::Reveal).await
+    }
+
+    pub async fn reset(&mut self) -> Result<(), ClientError> {
+        self.send(ClientMessage::Reset).await
+    }
+
+    pub async fn leave(&mut self) -> Result<(), ClientError> {
+        self.send(ClientMessage::Leave).await
+    }
+}
@@ -0,0 +1,45 @@
+use redis::AsyncCommands;
+use uuid::Uuid;
+
+/// Shared room directory for running more than one server instance
+/// behind a load balancer. A room's live state still lives only in the
+/// memory of whichever instance created it (see `AppState::rooms`) —
+/// this directory just answers "which instance has it" so a client that
+/// reaches the wrong one can be told where to reconnect, instead of a
+/// bare 404. Full state replication (so any instance could serve any
+/// room) would need the rooms map itself to move into Redis and is out
+/// of scope here.
+pub struct ClusterDirectory {
+    client: redis::Client,
+    self_url: String,
+}
+
+fn room_key(room_id: Uuid) -> String {
+    format!("poker:room_owner:{room_id}")
+}
+
+impl ClusterDirectory {
+    pub fn new(redis_url: &str, self_url: String) -> redis::RedisResult<Self> {
+        Ok(Self { client: redis::Client::open(redis_url)?, self_url })
+    }
+
+    /// Records that this instance owns `room_id`. Expires after a day so
+    /// a crashed instance's entries eventually fall out of the directory
+    /// without needing an explicit unregister on every shutdown path.
+    pub async fn register_room(&self, room_id: Uuid) -> redis::RedisResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        conn.set_ex(room_key(room_id), &self.self_url, 24 * 60 * 60).await
+    }
+
+    pub async fn unregister_room(&self, room_id: Uuid) -> redis::RedisResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        conn.del(room_key(room_id)).await
+    }
+
+    /// Base URL of whichever instance owns `room_id`, if any instance has
+    /// registered it (possibly this one).
+    pub async fn locate_room(&self, room_id: Uuid) -> redis::RedisResult<Option<String>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        conn.get(room_key(room_id)).await
+    }
+}
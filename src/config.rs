@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+/// Top-level server configuration, loaded from `config.toml` (or
+/// whatever path `POKER_CONFIG` points at) if present. Every field has a
+/// sane default, so a missing or partially invalid file degrades rather
+/// than prevents startup.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Config {
+    #[serde(default)]
+    pub listen_addr: Option<String>,
+    #[serde(default)]
+    pub default_deck: Option<Vec<String>>,
+    /// Path to a SQLite database file. When set, rooms are periodically
+    /// snapshotted there (see `persistence::run`) and reloaded on the
+    /// next startup instead of starting empty.
+    #[serde(default)]
+    pub sqlite_path: Option<String>,
+    /// Path to a JSON file rooms are periodically dumped to and restored
+    /// from on startup — a lighter-weight alternative to `sqlite_path`
+    /// for instances that don't want an embedded database file. Ignored
+    /// when `sqlite_path` is also set.
+    #[serde(default)]
+    pub snapshot_path: Option<String>,
+    /// How often to write `snapshot_path`, in seconds. Defaults to 60.
+    #[serde(default)]
+    pub snapshot_interval_secs: Option<u64>,
+    /// Redis URL for the multi-instance room directory (see
+    /// `cluster.rs`). Only needed when running more than one server
+    /// process behind a load balancer.
+    #[serde(default)]
+    pub redis_url: Option<String>,
+    /// Whether `/api/admin/metrics` breaks usage down per room (message
+    /// rate, user count) as well as reporting instance-wide totals. Off
+    /// by default since a deployment with many short-lived rooms could
+    /// otherwise blow up a scraper's label cardinality.
+    #[serde(default)]
+    pub metrics_per_room: bool,
+    /// Cap on how many rooms get their own per-room metric labels when
+    /// `metrics_per_room` is on (busiest rooms first); the rest are still
+    /// counted in the instance-wide totals. Defaults to 50.
+    #[serde(default)]
+    pub metrics_room_label_cap: Option<usize>,
+    /// Approximate per-room memory ceiling in bytes (stories, votes,
+    /// attachments). When set, adding a story or casting a vote that
+    /// would exceed it first evicts the oldest finished stories, and
+    /// refuses the request with an error if that still isn't enough.
+    /// `None` means no limit, the default.
+    #[serde(default)]
+    pub room_memory_budget_bytes: Option<usize>,
+    /// How often the server sends a WebSocket ping frame on each
+    /// connection, in seconds. Defaults to 30.
+    #[serde(default)]
+    pub heartbeat_interval_secs: Option<u64>,
+    /// How long a connection may go without a pong before it's treated as
+    /// a ghost: the participant is dropped from the room (so it can't
+    /// block `Room::all_eligible_voted`) and the socket is closed.
+    /// Defaults to 90.
+    #[serde(default)]
+    pub heartbeat_timeout_secs: Option<u64>,
+    /// Fault-injection knobs for exercising reconnect/resync logic in
+    /// staging or integration tests (see `chaos.rs`). Every field
+    /// defaults to off, so an absent `[chaos]` section never changes
+    /// behavior.
+    #[serde(default)]
+    pub chaos: ChaosConfig,
+    /// How often (in seconds) to send each voter with pending stories a
+    /// reminder digest via the configured `Notifier`s (see
+    /// `reminders.rs`). `None` (the default) disables reminders entirely.
+    #[serde(default)]
+    pub vote_reminder_interval_secs: Option<u64>,
+    /// How long (in seconds) the room owner may go without activity
+    /// before the background sweep (see `cleanup::run`) automatically
+    /// hands ownership to the longest-connected other participant, so a
+    /// closed laptop doesn't permanently strand the room. `None` (the
+    /// default) disables automatic failover entirely.
+    #[serde(default)]
+    pub owner_failover_grace_secs: Option<u64>,
+    /// Named decks an owner can switch to with
+    /// `ClientMessage::SelectDeckPreset`, e.g.:
+    /// ```toml
+    /// [deck_presets]
+    /// fibonacci = ["0", "1", "2", "3", "5", "8", "13", "21", "?"]
+    /// tshirt = ["XS", "S", "M", "L", "XL"]
+    /// ```
+    /// Empty by default — rooms created through `POST /api/rooms` start
+    /// on `default_deck` (or the built-in deck, which is also where
+    /// scheduled rooms start) until the owner picks one of these, or sets
+    /// a custom deck with `SetDeck`.
+    #[serde(default)]
+    pub deck_presets: HashMap<String, Vec<String>>,
+    /// How long (in seconds) a room may go without any WebSocket activity
+    /// before the background sweep (see `cleanup::run`) removes it.
+    /// `None` (the default) falls back to `room::ROOM_IDLE_TTL`.
+    #[serde(default)]
+    pub room_idle_ttl_secs: Option<u64>,
+    /// How long (in seconds) a participant, connected or not, may go
+    /// without activity before the background sweep (see `cleanup::run`)
+    /// drops them from `Room::users` entirely, so a grey "still here" entry
+    /// doesn't linger forever or keep blocking `Room::all_eligible_voted`.
+    /// `None` (the default) falls back to `room::PARTICIPANT_IDLE_TTL`.
+    #[serde(default)]
+    pub participant_idle_ttl_secs: Option<u64>,
+    /// Caps total concurrent rooms on this instance. `None` (the default)
+    /// means no limit — only appropriate for an instance that isn't
+    /// exposed to the open internet.
+    #[serde(default)]
+    pub max_rooms: Option<usize>,
+    /// Caps how many users (of any role) can be in a single room at once.
+    /// `None` (the default) means no limit.
+    #[serde(default)]
+    pub max_users_per_room: Option<usize>,
+    /// Named room templates (deck, auto-reveal delay, pre-seeded stories)
+    /// selectable by name via `CreateRoomRequest::template`, so teams get
+    /// a consistent setup without configuring each room by hand. Distinct
+    /// from the runtime templates saved with
+    /// `ClientMessage::SaveAsTemplate` (`state.templates`), which live in
+    /// memory and are created from an existing room rather than the
+    /// config file:
+    /// ```toml
+    /// [room_templates.planning]
+    /// deck = ["1", "2", "3", "5", "8", "?"]
+    /// auto_reveal_delay_secs = 5
+    ///
+    /// [[room_templates.planning.stories]]
+    /// title = "Warm-up story"
+    /// ```
+    #[serde(default)]
+    pub room_templates: HashMap<String, RoomTemplateConfig>,
+    /// JIRA instance to pull stories from via
+    /// `ClientMessage::ImportJira`. `None` (the default) leaves that
+    /// command rejected with "not configured".
+    #[serde(default)]
+    pub jira: Option<JiraConfig>,
+    /// GitHub instance to pull issues from via
+    /// `ClientMessage::ImportGithub`. `None` (the default) leaves that
+    /// command rejected with "not configured".
+    #[serde(default)]
+    pub github: Option<GithubConfig>,
+    /// Outbound webhooks notified on room events (room created, story
+    /// finished, session ended) — see `notify::WebhookNotifier`. Empty by
+    /// default, the same as having no notifiers registered at all.
+    /// ```toml
+    /// [[webhooks]]
+    /// url = "https://example.com/hooks/poker"
+    /// secret = "shh"
+    /// ```
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Default Slack incoming-webhook URL notified when a story finishes
+    /// (see `notify::SlackNotifier`). A room can override this with
+    /// `RoomConfig::slack_webhook_url`. `None` (the default) disables
+    /// Slack notifications for rooms without their own override.
+    #[serde(default)]
+    pub slack_webhook_url: Option<String>,
+    /// When true, `ClientMessage::AddStory` with a bare URL as the title
+    /// and no description fetches that page's `<title>` and uses it as
+    /// the description, so people can paste ticket links without typing
+    /// a description by hand. Off by default — fetching arbitrary
+    /// user-supplied URLs server-side has SSRF implications operators
+    /// should opt into deliberately.
+    #[serde(default)]
+    pub fetch_page_titles: bool,
+}
+
+/// See `Config::webhooks`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// When set, every delivery is signed with this as an HMAC-SHA256
+    /// key (see `notify::WebhookNotifier::send`) so the receiver can
+    /// verify the POST actually came from this server.
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+/// See `Config::github`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubConfig {
+    /// Personal access token sent as a bearer token on every request to
+    /// the GitHub REST API.
+    pub api_token: String,
+}
+
+/// See `Config::jira`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JiraConfig {
+    /// e.g. `https://your-team.atlassian.net`, no trailing slash required.
+    pub base_url: String,
+    /// Bearer token sent with every request to the JIRA REST API.
+    pub api_token: String,
+    /// Custom field ID for story points (e.g. `"customfield_10016"`),
+    /// since JIRA doesn't expose it under a stable well-known name. Used
+    /// by `jira::write_estimate` when a room has
+    /// `RoomConfig::jira_writeback` enabled.
+    pub story_points_field: String,
+}
+
+/// See `Config::room_templates`.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct RoomTemplateConfig {
+    #[serde(default)]
+    pub deck: Option<Vec<String>>,
+    #[serde(default)]
+    pub auto_reveal_delay_secs: Option<u64>,
+    #[serde(default)]
+    pub stories: Vec<RoomTemplateStoryConfig>,
+}
+
+/// A single pre-seeded story within a `RoomTemplateConfig`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoomTemplateStoryConfig {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// See `Config::chaos`. All-zero/`None` by default, which disables every
+/// fault this module can inject.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ChaosConfig {
+    /// Probability (0.0-1.0) that an outbound message to a connection is
+    /// silently dropped instead of sent.
+    #[serde(default)]
+    pub drop_broadcast_probability: f64,
+    /// Extra artificial latency added before every outbound send, in
+    /// milliseconds.
+    #[serde(default)]
+    pub extra_latency_ms: Option<u64>,
+    /// Probability (0.0-1.0), checked on each heartbeat tick, that a
+    /// healthy connection is forcibly disconnected anyway.
+    #[serde(default)]
+    pub forced_disconnect_probability: f64,
+}
+
+/// A single problem found while validating a loaded config, kept
+/// alongside the field path so operators can find it in their file.
+#[derive(Debug)]
+pub struct ValidationIssue {
+    pub field: String,
+    pub message: String,
+}
+
+/// Report produced by `Config::load`: the config to actually run with
+/// (defaults substituted for anything invalid) plus every issue found,
+/// so the caller can log them without treating them as fatal.
+pub struct LoadReport {
+    pub config: Config,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl Config {
+    /// Loads and validates the config file at `path`. Parse failures and
+    /// individual invalid fields are collected into `issues` rather than
+    /// aborting startup — the server runs with defaults for whatever
+    /// couldn't be trusted.
+    pub fn load(path: &str) -> LoadReport {
+        let raw = match std::fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(_) => return LoadReport { config: Config::default(), issues: Vec::new() },
+        };
+
+        let mut config: Config = match toml::from_str(&raw) {
+            Ok(config) => config,
+            Err(err) => {
+                return LoadReport {
+                    config: Config::default(),
+                    issues: vec![ValidationIssue {
+                        field: path.to_string(),
+                        message: format!("failed to parse config, using defaults: {err}"),
+                    }],
+                };
+            }
+        };
+
+        let mut issues = Vec::new();
+        if let Some(deck) = &config.default_deck {
+            if deck.is_empty() {
+                issues.push(ValidationIssue {
+                    field: "default_deck".to_string(),
+                    message: "must not be empty, falling back to the built-in deck".to_string(),
+                });
+                config.default_deck = None;
+            }
+        }
+
+        LoadReport { config, issues }
+    }
+}
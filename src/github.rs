@@ -0,0 +1,55 @@
+use serde::Deserialize;
+
+use crate::config::GithubConfig;
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    number: u64,
+    title: String,
+    html_url: String,
+    /// Present (non-null) on pull requests, which the issues endpoint
+    /// also returns alongside actual issues.
+    #[serde(default)]
+    pull_request: Option<serde_json::Value>,
+}
+
+/// One open issue fetched from GitHub, ready to become a `Story`.
+pub struct FetchedIssue {
+    pub title: String,
+    pub description: String,
+    pub url: String,
+}
+
+/// Fetches open issues from `repo` (`owner/name`), optionally filtered by
+/// label and/or milestone, for `ClientMessage::ImportGithub`. Pull
+/// requests are filtered out since GitHub's issues endpoint returns both.
+pub async fn fetch(
+    config: &GithubConfig,
+    repo: &str,
+    label: Option<&str>,
+    milestone: Option<&str>,
+) -> Result<Vec<FetchedIssue>, String> {
+    let mut url = format!("https://api.github.com/repos/{repo}/issues?state=open");
+    if let Some(label) = label {
+        url.push_str(&format!("&labels={label}"));
+    }
+    if let Some(milestone) = milestone {
+        url.push_str(&format!("&milestone={milestone}"));
+    }
+    let response = reqwest::Client::new()
+        .get(url)
+        .bearer_auth(&config.api_token)
+        .header("User-Agent", "ud1-poker")
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("github returned {}", response.status()));
+    }
+    let issues: Vec<Issue> = response.json().await.map_err(|err| err.to_string())?;
+    Ok(issues
+        .into_iter()
+        .filter(|issue| issue.pull_request.is_none())
+        .map(|issue| FetchedIssue { title: format!("{repo}#{}", issue.number), description: issue.title, url: issue.html_url })
+        .collect())
+}
@@ -0,0 +1,1388 @@
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::room::{Role, Room, RoomConfig, Story, User, Vote, PARTICIPANT_IDLE_TTL, ROOM_IDLE_TTL};
+use crate::state::AppState;
+use crate::ws::{ClientMessage, CloseReason, ErrorCode, IncomingMessage, ServerMessage, CURRENT_PROTOCOL_VERSION};
+
+/// Whether `user` should appear in the participant list sent to clients,
+/// per `RoomConfig::hide_watchers` and `RoomConfig::hide_owner`. Used
+/// everywhere the user list is built so resync, full `RoomState`
+/// broadcasts, and `UsersDelta` diffs never disagree on who's visible.
+fn user_is_visible(config: &RoomConfig, user: &User) -> bool {
+    !(config.hide_watchers && user.role == Role::Watcher) && !(config.hide_owner && user.role == Role::Owner)
+}
+
+/// Parses one incoming client frame, accepting both the current typed
+/// JSON envelope and, for one release cycle while clients migrate, the
+/// legacy `"<type> <json-payload>"` framing this protocol used before
+/// the envelope existed. Returns whether the legacy path was used so the
+/// caller can track it in `AppState::legacy_protocol_messages`.
+fn parse_incoming(text: &str) -> (Result<IncomingMessage, serde_json::Error>, bool) {
+    match serde_json::from_str::<IncomingMessage>(text) {
+        Ok(incoming) => (Ok(incoming), false),
+        Err(err) => match parse_legacy_message(text) {
+            Some(message) => (Ok(IncomingMessage { request_id: None, message }), true),
+            None => (Err(err), false),
+        },
+    }
+}
+
+/// Bridges the legacy `"<type> <json-payload-without-type>"` framing to
+/// `ClientMessage` by splicing the prefix back in as the envelope's
+/// `type` tag before handing it to serde. `None` if `text` doesn't even
+/// look like the legacy shape (no space, or the remainder isn't a JSON
+/// object), so the caller falls back to reporting the original JSON
+/// parse error instead of a confusing legacy-specific one.
+fn parse_legacy_message(text: &str) -> Option<ClientMessage> {
+    let (prefix, rest) = text.split_once(' ')?;
+    let mut value: serde_json::Value = serde_json::from_str(rest.trim()).ok()?;
+    value.as_object_mut()?.insert("type".to_string(), serde_json::Value::String(prefix.to_string()));
+    serde_json::from_value(value).ok()
+}
+
+/// Normalizes a display name for ban/unban matching. There's no stable
+/// per-client identity in this protocol (every `Join` gets a fresh
+/// `Uuid`), so a ban is keyed on the name the client typed — trimmed and
+/// lowercased so "Bob" and " bob " match, though a determined user can
+/// still evade a ban by picking a different name.
+fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Whether `title` is nothing but a URL, for `ClientMessage::AddStory`'s
+/// "paste a bare link" page-title fetch (see `Config::fetch_page_titles`).
+fn is_bare_url(title: &str) -> bool {
+    let title = title.trim();
+    title.starts_with("http://") || title.starts_with("https://")
+}
+
+/// Builds a private error reply naming the client message that triggered
+/// it, so the UI can show why a specific action failed instead of a bare
+/// "forbidden" with no context.
+fn error_reply(code: ErrorCode, command: &str, message: impl Into<String>) -> ServerMessage {
+    ServerMessage::Error { code, message: message.into(), command: Some(command.into()) }
+}
+
+/// Effective room idle TTL: `Config::room_idle_ttl_secs` if set, otherwise
+/// `ROOM_IDLE_TTL`. Shared with `cleanup::run` so the sweep and the
+/// `expires_at` it advertises to clients never disagree.
+pub(crate) fn room_idle_ttl(state: &AppState) -> Duration {
+    state.config.room_idle_ttl_secs.map(Duration::from_secs).unwrap_or(ROOM_IDLE_TTL)
+}
+
+/// Effective disconnected-participant TTL: `Config::participant_idle_ttl_secs`
+/// if set, otherwise `PARTICIPANT_IDLE_TTL`. Shared with `cleanup::run`'s
+/// reap sweep.
+pub(crate) fn participant_idle_ttl(state: &AppState) -> Duration {
+    state.config.participant_idle_ttl_secs.map(Duration::from_secs).unwrap_or(PARTICIPANT_IDLE_TTL)
+}
+
+/// Default `Config::heartbeat_interval_secs`.
+const DEFAULT_HEARTBEAT_INTERVAL_SECS: u64 = 30;
+/// Default `Config::heartbeat_timeout_secs`.
+const DEFAULT_HEARTBEAT_TIMEOUT_SECS: u64 = 90;
+
+/// Query params accepted on both WS routes for protocol version
+/// negotiation (see `ServerMessage::ProtocolNegotiated`).
+#[derive(Debug, Deserialize)]
+pub struct ConnectParams {
+    #[serde(default)]
+    protocol_version: Option<u32>,
+    /// Matched against `Room::password`, if the room has one set. Absent
+    /// or wrong gets the connection a `ServerMessage::JoinRejected` and an
+    /// immediate close rather than a participant slot.
+    #[serde(default)]
+    password: Option<String>,
+    /// Always required to connect `observe_route`: every room has a
+    /// spectator token from creation (see `Room::spectator_token`); a
+    /// mismatch or missing token gets the connection a
+    /// `ServerMessage::JoinRejected`.
+    #[serde(default)]
+    spectator_token: Option<String>,
+}
+
+/// The server never speaks a version newer than its own, and currently
+/// only speaks one version at all. The answer is the lower of the
+/// requested and current versions, so a client asking for something older
+/// is told that older number back, while messages stay in the current
+/// format.
+fn negotiate_protocol_version(requested: Option<u32>) -> u32 {
+    requested.unwrap_or(CURRENT_PROTOCOL_VERSION).min(CURRENT_PROTOCOL_VERSION)
+}
+
+/// Sends a close frame carrying `reason`'s code/text. Best-effort: if the
+/// client is already gone there's nobody to receive it.
+async fn close_with(socket: &mut WebSocket, reason: CloseReason) {
+    let _ = socket
+        .send(Message::Close(Some(axum::extract::ws::CloseFrame {
+            code: reason.code(),
+            reason: reason.reason_text().into(),
+        })))
+        .await;
+}
+
+pub async fn ws_route(
+    ws: WebSocketUpgrade,
+    Path(room_id): Path<Uuid>,
+    Query(params): Query<ConnectParams>,
+    State(state): State<AppState>,
+) -> Response {
+    if let Some(hint) = other_instance_hint(&state, room_id).await {
+        return hint;
+    }
+    let protocol_version = negotiate_protocol_version(params.protocol_version);
+    ws.on_upgrade(move |socket| handle_socket(socket, room_id, state, protocol_version, params.password))
+}
+
+/// If `room_id` isn't hosted locally but the cluster directory (see
+/// `cluster.rs`) knows another instance has it, returns a 404 whose
+/// message names that instance instead of upgrading into a socket that
+/// will just say "room not found". `None` means proceed with the
+/// upgrade as usual (room is local, or no cluster is configured, or
+/// nobody has the room at all).
+async fn other_instance_hint(state: &AppState, room_id: Uuid) -> Option<Response> {
+    if state.rooms.read().await.contains_key(&room_id) {
+        return None;
+    }
+    let cluster = state.cluster.as_ref()?;
+    let location = cluster.locate_room(room_id).await.ok().flatten()?;
+    Some(
+        crate::error::ApiError::not_found(format!("this room is hosted at {location}, reconnect there instead"))
+            .with_retry_after(5)
+            .into_response(),
+    )
+}
+
+/// Read-only counterpart to `ws_route` for dashboards/monitoring: it
+/// gets the same broadcast stream but can never send a `ClientMessage`,
+/// so it never shows up in the participant list and can't vote.
+pub async fn observe_route(
+    ws: WebSocketUpgrade,
+    Path(room_id): Path<Uuid>,
+    Query(params): Query<ConnectParams>,
+    State(state): State<AppState>,
+) -> Response {
+    if let Some(hint) = other_instance_hint(&state, room_id).await {
+        return hint;
+    }
+    let protocol_version = negotiate_protocol_version(params.protocol_version);
+    ws.on_upgrade(move |socket| handle_observer_socket(socket, room_id, state, protocol_version, params.password, params.spectator_token))
+}
+
+/// Checks `attempt` against a password-protected room's `Room::password`,
+/// rejecting with `ServerMessage::JoinRejected` and closing the socket if
+/// it's missing or wrong. Returns whether the caller should keep going.
+async fn check_room_password(socket: &mut WebSocket, state: &AppState, room_id: Uuid, attempt: &Option<String>) -> bool {
+    let required = state.rooms.read().await.get(&room_id).and_then(|room| room.password.clone());
+    let Some(required) = required else { return true };
+    if attempt.as_deref() == Some(required.as_str()) {
+        return true;
+    }
+    let _ = socket
+        .send(Message::Text(
+            serde_json::to_string(&ServerMessage::JoinRejected { reason: "this room requires a password".into() }).unwrap(),
+        ))
+        .await;
+    close_with(socket, CloseReason::ProtocolError).await;
+    false
+}
+
+/// Checks `attempt` against `Room::spectator_token`, rejecting with
+/// `ServerMessage::JoinRejected` and closing the socket if it's missing or
+/// wrong. Unlike `check_room_password`, every room has a spectator token —
+/// there's no "observing requires no token" mode.
+async fn check_spectator_token(socket: &mut WebSocket, state: &AppState, room_id: Uuid, attempt: &Option<String>) -> bool {
+    let required = state.rooms.read().await.get(&room_id).map(|room| room.spectator_token.clone());
+    let Some(required) = required else { return true };
+    if attempt.as_deref() == Some(required.as_str()) {
+        return true;
+    }
+    let _ = socket
+        .send(Message::Text(
+            serde_json::to_string(&ServerMessage::JoinRejected { reason: "a valid spectator link is required to observe this room".into() })
+                .unwrap(),
+        ))
+        .await;
+    close_with(socket, CloseReason::ProtocolError).await;
+    false
+}
+
+async fn handle_observer_socket(
+    mut socket: WebSocket,
+    room_id: Uuid,
+    state: AppState,
+    protocol_version: u32,
+    password: Option<String>,
+    spectator_token: Option<String>,
+) {
+    let Some(mut receiver) = state.rooms.read().await.get(&room_id).map(|r| r.sender.subscribe()) else {
+        let _ = socket
+            .send(Message::Text(
+                serde_json::to_string(&ServerMessage::Error {
+                    code: ErrorCode::NotFound,
+                    message: "room not found".into(),
+                    command: None,
+                })
+                .unwrap(),
+            ))
+            .await;
+        return;
+    };
+    if !check_room_password(&mut socket, &state, room_id, &password).await {
+        return;
+    }
+    if !check_spectator_token(&mut socket, &state, room_id, &spectator_token).await {
+        return;
+    }
+    let _ = socket
+        .send(Message::Text(serde_json::to_string(&ServerMessage::ProtocolNegotiated { version: protocol_version }).unwrap()))
+        .await;
+    if let Some(state) = resync(&state, room_id).await {
+        let _ = socket.send(Message::Text(serde_json::to_string(&state).unwrap())).await;
+    }
+    let heartbeat_interval = Duration::from_secs(state.config.heartbeat_interval_secs.unwrap_or(DEFAULT_HEARTBEAT_INTERVAL_SECS));
+    let heartbeat_timeout = Duration::from_secs(state.config.heartbeat_timeout_secs.unwrap_or(DEFAULT_HEARTBEAT_TIMEOUT_SECS));
+    let mut ping_ticker = tokio::time::interval(heartbeat_interval);
+    let mut last_pong = std::time::Instant::now();
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => {
+                match incoming {
+                    Some(Ok(Message::Pong(_))) => last_pong = std::time::Instant::now(),
+                    // Observers can disconnect but have nothing else to send us.
+                    None => break,
+                    _ => {}
+                }
+            }
+            outgoing = receiver.recv() => {
+                match outgoing {
+                    Ok(ServerMessage::RoomClosing { reason }) => {
+                        close_with(&mut socket, reason).await;
+                        break;
+                    }
+                    // An observer isn't a participant, so a disconnect
+                    // targeted at one specific user is never about it,
+                    // and it can never be the room owner either.
+                    Ok(ServerMessage::ForceDisconnect { .. }) | Ok(ServerMessage::OwnerLiveVotes { .. }) => {}
+                    Ok(msg) => {
+                        crate::chaos::inject_latency(&state.config.chaos).await;
+                        if crate::chaos::should_drop_broadcast(&state.config.chaos) {
+                            continue;
+                        }
+                        if socket.send(Message::Text(serde_json::to_string(&msg).unwrap())).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(_)) => {
+                        if let Some(state) = resync(&state, room_id).await {
+                            let _ = socket.send(Message::Text(serde_json::to_string(&state).unwrap())).await;
+                        }
+                    }
+                }
+            }
+            _ = ping_ticker.tick() => {
+                if crate::chaos::should_force_disconnect(&state.config.chaos) {
+                    close_with(&mut socket, CloseReason::ServerShutdown).await;
+                    break;
+                }
+                if last_pong.elapsed() > heartbeat_timeout {
+                    close_with(&mut socket, CloseReason::IdleTimeout).await;
+                    break;
+                }
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+async fn handle_socket(mut socket: WebSocket, room_id: Uuid, state: AppState, protocol_version: u32, password: Option<String>) {
+    let mut receiver = {
+        let rooms = state.rooms.read().await;
+        match rooms.get(&room_id) {
+            Some(room) => room.sender.subscribe(),
+            None => {
+                let _ = socket
+                    .send(Message::Text(
+                        serde_json::to_string(&ServerMessage::Error {
+                            code: ErrorCode::NotFound,
+                            message: "room not found".into(),
+                            command: None,
+                        })
+                        .unwrap(),
+                    ))
+                    .await;
+                return;
+            }
+        }
+    };
+    if !check_room_password(&mut socket, &state, room_id, &password).await {
+        return;
+    }
+    let _ = socket
+        .send(Message::Text(serde_json::to_string(&ServerMessage::ProtocolNegotiated { version: protocol_version }).unwrap()))
+        .await;
+
+    let heartbeat_interval = Duration::from_secs(state.config.heartbeat_interval_secs.unwrap_or(DEFAULT_HEARTBEAT_INTERVAL_SECS));
+    let heartbeat_timeout = Duration::from_secs(state.config.heartbeat_timeout_secs.unwrap_or(DEFAULT_HEARTBEAT_TIMEOUT_SECS));
+    let mut ping_ticker = tokio::time::interval(heartbeat_interval);
+    let mut last_pong = std::time::Instant::now();
+
+    let mut user_id = None;
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => {
+                match incoming {
+                    Some(Ok(Message::Pong(_))) => last_pong = std::time::Instant::now(),
+                    Some(Ok(Message::Text(text))) => {
+                        let (parsed, is_legacy) = parse_incoming(&text);
+                        if is_legacy {
+                            state.legacy_protocol_messages.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        }
+                        match parsed {
+                            Ok(incoming) => {
+                                let request_id = incoming.request_id;
+                                let (next_user, reply) = handle_client_message(&state, room_id, user_id, incoming.message).await;
+                                user_id = next_user;
+                                let rejected_reason = match &reply {
+                                    Some(ServerMessage::Error { message, .. }) => Some(message.clone()),
+                                    _ => None,
+                                };
+                                if let Some(reply) = reply {
+                                    let _ = socket.send(Message::Text(serde_json::to_string(&reply).unwrap())).await;
+                                }
+                                if let Some(request_id) = request_id {
+                                    let ack_or_nack = match rejected_reason {
+                                        Some(reason) => ServerMessage::Nack { request_id, reason },
+                                        None => ServerMessage::Ack { request_id },
+                                    };
+                                    let _ = socket.send(Message::Text(serde_json::to_string(&ack_or_nack).unwrap())).await;
+                                }
+                            }
+                            Err(err) => {
+                                warn!(%err, "dropping malformed client message");
+                                let offending_command = serde_json::from_str::<serde_json::Value>(&text)
+                                    .ok()
+                                    .and_then(|v| v.get("type").and_then(|t| t.as_str()).map(str::to_string));
+                                let reply = ServerMessage::Error {
+                                    code: ErrorCode::InvalidMessage,
+                                    message: format!("couldn't parse message: {err}"),
+                                    command: offending_command,
+                                };
+                                let _ = socket.send(Message::Text(serde_json::to_string(&reply).unwrap())).await;
+                            }
+                        }
+                    }
+                    // Incoming pings are answered transparently below axum;
+                    // a binary frame has no meaning in this JSON-over-text
+                    // protocol, so treat it as a protocol violation rather
+                    // than silently ignoring or hanging up with no reason.
+                    Some(Ok(Message::Binary(_))) => {
+                        close_with(&mut socket, CloseReason::ProtocolError).await;
+                        break;
+                    }
+                    Some(Ok(Message::Ping(_))) => {}
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => {
+                        if let Some(id) = user_id {
+                            drop_silent_participant(&state, room_id, id).await;
+                        }
+                        break;
+                    }
+                }
+            }
+            outgoing = receiver.recv() => {
+                match outgoing {
+                    Ok(ServerMessage::RoomClosing { reason }) => {
+                        close_with(&mut socket, reason).await;
+                        break;
+                    }
+                    Ok(ServerMessage::ForceDisconnect { user_id: target, reason }) => {
+                        if Some(target) == user_id {
+                            close_with(&mut socket, reason).await;
+                            break;
+                        }
+                        // Meant for a different connection in this room.
+                    }
+                    Ok(ServerMessage::OwnerLiveVotes { for_user_id, story_id, votes }) => {
+                        if Some(for_user_id) == user_id {
+                            let msg = ServerMessage::OwnerLiveVotes { for_user_id, story_id, votes };
+                            if socket.send(Message::Text(serde_json::to_string(&msg).unwrap())).await.is_err() {
+                                break;
+                            }
+                        }
+                        // Meant only for the room owner's connection.
+                    }
+                    Ok(msg) => {
+                        crate::chaos::inject_latency(&state.config.chaos).await;
+                        if crate::chaos::should_drop_broadcast(&state.config.chaos) {
+                            continue;
+                        }
+                        if socket.send(Message::Text(serde_json::to_string(&msg).unwrap())).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(_)) => {
+                        // Missed some broadcasts while behind; a full
+                        // RoomState resync is cheaper than tracking which
+                        // messages were dropped.
+                        if let Some(state) = resync(&state, room_id).await {
+                            if socket.send(Message::Text(serde_json::to_string(&state).unwrap())).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            _ = ping_ticker.tick() => {
+                if crate::chaos::should_force_disconnect(&state.config.chaos) {
+                    if let Some(id) = user_id {
+                        drop_silent_participant(&state, room_id, id).await;
+                    }
+                    close_with(&mut socket, CloseReason::ServerShutdown).await;
+                    break;
+                }
+                if last_pong.elapsed() > heartbeat_timeout {
+                    if let Some(id) = user_id {
+                        drop_silent_participant(&state, room_id, id).await;
+                    }
+                    close_with(&mut socket, CloseReason::IdleTimeout).await;
+                    break;
+                }
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Removes a participant whose connection stopped answering heartbeat
+/// pings, the same way `ClientMessage::Leave` does when the client says
+/// so explicitly — so a ghost tab can't sit in the user list forever
+/// blocking `Room::all_eligible_voted`.
+async fn drop_silent_participant(state: &AppState, room_id: Uuid, user_id: Uuid) {
+    let mut rooms = state.rooms.write().await;
+    let Some(room) = rooms.get_mut(&room_id) else { return };
+    let before = visible_users(room);
+    room.users.remove(&user_id);
+    for story in &mut room.stories {
+        story.votes.remove(&user_id);
+    }
+    broadcast_users_delta(room, before);
+}
+
+/// Fires after the configured auto-reveal delay. Re-checks that the same
+/// story is still selected and still fully voted before revealing, so a
+/// reset or a `SelectStory` in the meantime doesn't reveal the wrong
+/// thing.
+async fn auto_reveal_if_still_eligible(state: &AppState, room_id: Uuid, story_id: Uuid) {
+    let mut rooms = state.rooms.write().await;
+    let Some(room) = rooms.get_mut(&room_id) else { return };
+    let still_current = room.current_story.and_then(|i| room.stories.get(i)).is_some_and(|s| s.id == story_id);
+    if !still_current || !room.all_eligible_voted() {
+        return;
+    }
+    if let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) {
+        story.revealed = true;
+        story.phase = crate::room::StoryPhase::Revealed;
+    }
+    broadcast_story_update(room, story_id);
+    room.broadcast(ServerMessage::Notify { hint: crate::ws::NotificationHint::Revealed });
+    broadcast_session_advisories(room);
+}
+
+/// Background task behind `ClientMessage::StartTimer`: broadcasts a
+/// `TimerTick` once per second and auto-reveals the story when it hits
+/// zero, unless the story was revealed (or removed) in the meantime, in
+/// which case it just quietly stops.
+async fn run_story_timer(state: AppState, room_id: Uuid, story_id: Uuid, mut seconds_remaining: u64) {
+    loop {
+        {
+            let mut rooms = state.rooms.write().await;
+            let Some(room) = rooms.get_mut(&room_id) else { return };
+            let Some(story) = room.stories.iter().find(|s| s.id == story_id) else { return };
+            if story.revealed {
+                return;
+            }
+            room.broadcast(ServerMessage::TimerTick { story_id, seconds_remaining });
+        }
+        if seconds_remaining == 0 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        seconds_remaining -= 1;
+    }
+    let mut rooms = state.rooms.write().await;
+    let Some(room) = rooms.get_mut(&room_id) else { return };
+    let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) else { return };
+    if story.revealed {
+        return;
+    }
+    story.revealed = true;
+    story.phase = crate::room::StoryPhase::Revealed;
+    broadcast_story_update(room, story_id);
+    room.broadcast(ServerMessage::Notify { hint: crate::ws::NotificationHint::Revealed });
+    broadcast_session_advisories(room);
+}
+
+/// Builds a fresh `RoomState` for a single client that fell behind on
+/// the broadcast channel, without re-broadcasting it to everyone else.
+async fn resync(state: &AppState, room_id: Uuid) -> Option<ServerMessage> {
+    let rooms = state.rooms.read().await;
+    let room = rooms.get(&room_id)?;
+    let users = room
+        .users
+        .values()
+        .filter(|u| user_is_visible(&room.config, u))
+        .cloned()
+        .collect();
+    Some(ServerMessage::RoomState {
+        seq: room.state_seq,
+        users,
+        stories: room.stories.iter().map(|s| s.view(&room.config, &room.users)).collect(),
+        current_story: room.current_story.and_then(|i| room.stories.get(i)).map(|s| s.id),
+        on_break: room.on_break,
+    })
+}
+
+/// Applies a client message to room state, returning the (possibly new)
+/// identity of the connection and an optional reply meant only for the
+/// sender (as opposed to the room-wide broadcasts most handlers emit).
+async fn handle_client_message(
+    state: &AppState,
+    room_id: Uuid,
+    current_user: Option<Uuid>,
+    msg: ClientMessage,
+) -> (Option<Uuid>, Option<ServerMessage>) {
+    let mut rooms = state.rooms.write().await;
+    let Some(room) = rooms.get_mut(&room_id) else { return (current_user, None) };
+    room.touch();
+    room.message_count += 1;
+    if let Some(user) = current_user.and_then(|id| room.users.get_mut(&id)) {
+        user.last_seen = std::time::Instant::now();
+    }
+
+    match msg {
+        ClientMessage::Join { name, role, guest_token, is_bot, owner_token } => {
+            if room.banned_names.contains(&normalize_name(&name)) {
+                return (current_user, Some(error_reply(ErrorCode::Forbidden, "join", "you have been banned from this room")));
+            }
+            let claims_owner_seat = owner_token.is_some_and(|token| token == room.owner_id);
+            let id = if claims_owner_seat { room.owner_id } else { current_user.unwrap_or_else(Uuid::new_v4) };
+            if let Some(max_users) = state.config.max_users_per_room {
+                if !room.users.contains_key(&id) && room.users.len() >= max_users {
+                    return (current_user, Some(error_reply(ErrorCode::Rejected, "join", "this room is full")));
+                }
+            }
+            let now = std::time::Instant::now();
+            let guest_locked = guest_token.is_some_and(|t| t == room.watcher_guest_token);
+            let role = if guest_locked { Role::Watcher } else if claims_owner_seat { Role::Owner } else { role };
+            let before = visible_users(room);
+            room.users.insert(id, User { id, name, role, last_seen: now, joined_at: now, guest_locked, is_bot });
+            broadcast_users_delta(room, before);
+            room.broadcast(room_config_message(room, room_idle_ttl(state)));
+            return (Some(id), None);
+        }
+        ClientMessage::Vote { story_id, value } => {
+            let story_ref = room.stories.iter().find(|s| s.id == story_id);
+            if let Some(story) = story_ref {
+                if !current_user.is_some_and(|id| story.is_eligible_voter(id)) {
+                    return (
+                        current_user,
+                        Some(error_reply(ErrorCode::Rejected, "vote", "this story isn't scoped to you")),
+                    );
+                }
+            }
+            if story_ref.is_some_and(|s| s.revealed) && !room.config.allow_vote_change_after_reveal {
+                return (current_user, Some(error_reply(ErrorCode::Rejected, "vote", "votes are locked once a story is revealed")));
+            }
+            let lateness = story_ref.map(Story::vote_lateness);
+            if let Some((_, true)) = lateness {
+                return (current_user, Some(error_reply(ErrorCode::Rejected, "vote", "voting deadline has passed")));
+            }
+            let late = lateness.map(|(late, _)| late).unwrap_or(false);
+            if let Some(budget) = state.config.room_memory_budget_bytes {
+                if !room.evict_to_fit(budget, value.len() + 48) {
+                    return (
+                        current_user,
+                        Some(error_reply(
+                            ErrorCode::Rejected,
+                            "vote",
+                            "room is over its memory budget; ask the owner to clear some finished stories",
+                        )),
+                    );
+                }
+            }
+            let owner_sees_live_votes = room.config.owner_sees_live_votes;
+            if let (Some(user_id), Some(story)) =
+                (current_user, room.stories.iter_mut().find(|s| s.id == story_id))
+            {
+                story.votes.insert(user_id, Vote { value, voted_at: chrono::Utc::now(), late });
+                let voted_user_ids = story.votes.keys().copied().collect();
+                let live_votes = (owner_sees_live_votes && !story.revealed).then(|| story.votes.clone());
+                room.broadcast(ServerMessage::VoteUpdate { story_id, voted_user_ids });
+                if let Some(votes) = live_votes {
+                    room.broadcast(ServerMessage::OwnerLiveVotes { for_user_id: room.owner_id, story_id, votes });
+                }
+                if room.all_eligible_voted() {
+                    room.broadcast(ServerMessage::Notify { hint: crate::ws::NotificationHint::AllVoted });
+                    match room.config.auto_reveal_delay_secs {
+                        None | Some(0) => {
+                            if let Some(story) = room.current_story.and_then(|i| room.stories.get_mut(i)) {
+                                story.revealed = true;
+                                story.phase = crate::room::StoryPhase::Revealed;
+                            }
+                            broadcast_room_state(room);
+                            room.broadcast(ServerMessage::Notify { hint: crate::ws::NotificationHint::Revealed });
+                            broadcast_session_advisories(room);
+                        }
+                        Some(delay_secs) => {
+                            let state = state.clone();
+                            tokio::spawn(async move {
+                                tokio::time::sleep(std::time::Duration::from_secs(delay_secs)).await;
+                                auto_reveal_if_still_eligible(&state, room_id, story_id).await;
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        ClientMessage::Reveal => {
+            match room.current_story.and_then(|i| room.stories.get_mut(i)) {
+                Some(story) => {
+                    story.revealed = true;
+                    story.phase = crate::room::StoryPhase::Revealed;
+                    let story_id = story.id;
+                    broadcast_story_update(room, story_id);
+                }
+                None => broadcast_room_state(room),
+            }
+            room.broadcast(ServerMessage::Notify { hint: crate::ws::NotificationHint::Revealed });
+            broadcast_session_advisories(room);
+        }
+        ClientMessage::Reset => {
+            match room.current_story.and_then(|i| room.stories.get_mut(i)) {
+                Some(story) => {
+                    story.revealed = false;
+                    story.votes.clear();
+                    story.phase = crate::room::StoryPhase::Voting;
+                    let story_id = story.id;
+                    broadcast_story_update(room, story_id);
+                }
+                None => broadcast_room_state(room),
+            }
+        }
+        ClientMessage::AddStory { title, description } => {
+            let story = Story::new(title.clone(), description.clone());
+            if let Some(budget) = state.config.room_memory_budget_bytes {
+                if !room.evict_to_fit(budget, story.approx_memory_bytes()) {
+                    return (
+                        current_user,
+                        Some(error_reply(
+                            ErrorCode::Rejected,
+                            "add_story",
+                            "room is over its memory budget and has no finished stories left to evict",
+                        )),
+                    );
+                }
+            }
+            let story_id = story.id;
+            room.stories.push(story);
+            broadcast_room_state(room);
+            if state.config.fetch_page_titles && description.is_empty() && is_bare_url(&title) {
+                let state = state.clone();
+                let url = title;
+                tokio::spawn(async move {
+                    match crate::pagefetch::fetch_title(&url).await {
+                        Ok(page_title) => {
+                            let mut rooms = state.rooms.write().await;
+                            let Some(room) = rooms.get_mut(&room_id) else { return };
+                            if let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) {
+                                story.set_description(page_title);
+                                story.set_story_url(Some(url));
+                                broadcast_story_update(room, story_id);
+                            }
+                        }
+                        Err(err) => warn!(%err, %url, "failed to fetch page title for story"),
+                    }
+                });
+            }
+        }
+        ClientMessage::SelectStory { story_id } => {
+            room.current_story = room.stories.iter().position(|s| s.id == story_id);
+            room.story_started_at = Some(std::time::Instant::now());
+            if room.current_story.is_some() {
+                broadcast_story_update(room, story_id);
+            } else {
+                broadcast_room_state(room);
+            }
+        }
+        ClientMessage::ReorderStories { story_ids } => {
+            if current_user != Some(room.owner_id) {
+                return (current_user, Some(error_reply(ErrorCode::Forbidden, "reorder_stories", "only the room owner can reorder stories")));
+            }
+            if story_ids.len() != room.stories.len()
+                || !story_ids.iter().all(|id| room.stories.iter().any(|s| s.id == *id))
+                || story_ids.iter().collect::<std::collections::HashSet<_>>().len() != story_ids.len()
+            {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::InvalidMessage, "reorder_stories", "story_ids must be a permutation of the room's current stories")),
+                );
+            }
+            let current_story_id = room.current_story.and_then(|i| room.stories.get(i)).map(|s| s.id);
+            let mut stories: Vec<Story> = Vec::with_capacity(room.stories.len());
+            for id in &story_ids {
+                let index = room.stories.iter().position(|s| s.id == *id).expect("checked above");
+                stories.push(room.stories.remove(index));
+            }
+            room.stories = stories;
+            room.current_story = current_story_id.and_then(|id| room.stories.iter().position(|s| s.id == id));
+            broadcast_room_state(room);
+        }
+        ClientMessage::Leave => {
+            if let Some(id) = current_user {
+                let before = visible_users(room);
+                room.users.remove(&id);
+                for story in &mut room.stories {
+                    story.votes.remove(&id);
+                }
+                broadcast_users_delta(room, before);
+            }
+            return (None, None);
+        }
+        ClientMessage::KeepAlive => {
+            // `touch()` above already reset the idle clock; just report
+            // the new expiry back so the client can update its countdown.
+            room.broadcast(room_config_message(room, room_idle_ttl(state)));
+        }
+        ClientMessage::SetPersistent { persistent } => {
+            if current_user == Some(room.owner_id) {
+                room.persistent = persistent;
+                room.broadcast(room_config_message(room, room_idle_ttl(state)));
+            } else {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "set_persistent", "only the room owner can change persistence")),
+                );
+            }
+        }
+        ClientMessage::ChangeRole { user_id, role } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "change_role", "only the room owner can change roles")),
+                );
+            }
+            let before = visible_users(room);
+            let Some(user) = room.users.get_mut(&user_id) else { return (current_user, None) };
+            if user.guest_locked && role != Role::Watcher {
+                return (
+                    current_user,
+                    Some(error_reply(
+                        ErrorCode::Rejected,
+                        "change_role",
+                        "this user joined via the watcher guest link and can't be promoted",
+                    )),
+                );
+            }
+            let previous_role = user.role;
+            user.role = role;
+            // A user who stops being a Voter can no longer have a vote
+            // standing in any story; clear it explicitly rather than
+            // leaving a stale vote from before the role change.
+            if previous_role == Role::Voter && role != Role::Voter {
+                for story in &mut room.stories {
+                    story.votes.remove(&user_id);
+                }
+            }
+            broadcast_users_delta(room, before);
+        }
+        ClientMessage::Kick { user_id } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "kick", "only the room owner can kick participants")),
+                );
+            }
+            if user_id == room.owner_id {
+                return (current_user, Some(error_reply(ErrorCode::Rejected, "kick", "the room owner can't kick themself")));
+            }
+            if !room.users.contains_key(&user_id) {
+                return (current_user, Some(error_reply(ErrorCode::NotFound, "kick", "no such participant")));
+            }
+            let before = visible_users(room);
+            room.users.remove(&user_id);
+            for story in &mut room.stories {
+                story.votes.remove(&user_id);
+            }
+            broadcast_users_delta(room, before);
+            room.broadcast(ServerMessage::ForceDisconnect { user_id, reason: CloseReason::Kicked });
+        }
+        ClientMessage::Ban { user_id } => {
+            if current_user != Some(room.owner_id) {
+                return (current_user, Some(error_reply(ErrorCode::Forbidden, "ban", "only the room owner can ban participants")));
+            }
+            if user_id == room.owner_id {
+                return (current_user, Some(error_reply(ErrorCode::Rejected, "ban", "the room owner can't ban themself")));
+            }
+            let Some(user) = room.users.get(&user_id) else {
+                return (current_user, Some(error_reply(ErrorCode::NotFound, "ban", "no such participant")));
+            };
+            room.banned_names.insert(normalize_name(&user.name));
+            let before = visible_users(room);
+            room.users.remove(&user_id);
+            for story in &mut room.stories {
+                story.votes.remove(&user_id);
+            }
+            broadcast_users_delta(room, before);
+            room.broadcast(ServerMessage::ForceDisconnect { user_id, reason: CloseReason::Banned });
+        }
+        ClientMessage::Unban { name } => {
+            if current_user != Some(room.owner_id) {
+                return (current_user, Some(error_reply(ErrorCode::Forbidden, "unban", "only the room owner can unban participants")));
+            }
+            room.banned_names.remove(&normalize_name(&name));
+        }
+        ClientMessage::TransferOwnership { user_id } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "transfer_ownership", "only the room owner can transfer ownership")),
+                );
+            }
+            if !room.users.contains_key(&user_id) {
+                return (current_user, Some(error_reply(ErrorCode::NotFound, "transfer_ownership", "no such participant")));
+            }
+            room.owner_id = user_id;
+            room.broadcast(ServerMessage::OwnershipTransferred { owner_id: user_id });
+        }
+        ClientMessage::SetStoryDeadline { story_id, deadline, late_vote_policy } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "set_story_deadline", "only the room owner can set a voting deadline")),
+                );
+            }
+            if let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) {
+                story.deadline = deadline;
+                story.late_vote_policy = late_vote_policy;
+                broadcast_story_update(room, story_id);
+            }
+        }
+        ClientMessage::SetDeck { deck } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "set_deck", "only the room owner can change the deck")),
+                );
+            }
+            for story in &mut room.stories {
+                story.migrate_votes(&deck);
+            }
+            room.config.deck = deck;
+            broadcast_room_state(room);
+            room.broadcast(room_config_message(room, room_idle_ttl(state)));
+        }
+        ClientMessage::SelectDeckPreset { name } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "select_deck_preset", "only the room owner can change the deck")),
+                );
+            }
+            let Some(deck) = state.config.deck_presets.get(&name).cloned() else {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::NotFound, "select_deck_preset", "no deck preset with that name is configured")),
+                );
+            };
+            for story in &mut room.stories {
+                story.migrate_votes(&deck);
+            }
+            room.config.deck = deck;
+            broadcast_room_state(room);
+            room.broadcast(room_config_message(room, room_idle_ttl(state)));
+        }
+        ClientMessage::UpdateRoomSettings(patch) => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "update_room_settings", "only the room owner can change room settings")),
+                );
+            }
+            if let Some(deck) = patch.deck {
+                for story in &mut room.stories {
+                    story.migrate_votes(&deck);
+                }
+                room.config.deck = deck;
+            }
+            if let Some(hide_watchers) = patch.hide_watchers {
+                room.config.hide_watchers = hide_watchers;
+            }
+            if let Some(auto_reveal_delay_secs) = patch.auto_reveal_delay_secs {
+                room.config.auto_reveal_delay_secs = Some(auto_reveal_delay_secs);
+            }
+            if let Some(owner_sees_live_votes) = patch.owner_sees_live_votes {
+                room.config.owner_sees_live_votes = owner_sees_live_votes;
+            }
+            if let Some(hide_owner) = patch.hide_owner {
+                room.config.hide_owner = hide_owner;
+            }
+            if let Some(allow_vote_change_after_reveal) = patch.allow_vote_change_after_reveal {
+                room.config.allow_vote_change_after_reveal = allow_vote_change_after_reveal;
+            }
+            if let Some(anonymous_reveal) = patch.anonymous_reveal {
+                room.config.anonymous_reveal = anonymous_reveal;
+            }
+            if let Some(jira_writeback) = patch.jira_writeback {
+                room.config.jira_writeback = jira_writeback;
+            }
+            if let Some(slack_webhook_url) = patch.slack_webhook_url {
+                room.config.slack_webhook_url = Some(slack_webhook_url);
+            }
+            if let Some(locale) = patch.locale {
+                room.config.locale = Some(locale);
+            }
+            broadcast_room_state(room);
+            room.broadcast(room_config_message(room, room_idle_ttl(state)));
+        }
+        ClientMessage::SetAttachments { story_id, attachments } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "set_attachments", "only the room owner can set story attachments")),
+                );
+            }
+            if let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) {
+                story.attachments = attachments;
+                broadcast_story_update(room, story_id);
+            }
+        }
+        ClientMessage::SetBreakMode { on_break } => {
+            if current_user == Some(room.owner_id) {
+                room.on_break = on_break;
+                broadcast_room_state(room);
+            } else {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "set_break_mode", "only the room owner can toggle break mode")),
+                );
+            }
+        }
+        ClientMessage::SetRoomName { name } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "set_room_name", "only the room owner can rename the room")),
+                );
+            }
+            room.name = name;
+            room.broadcast(room_config_message(room, room_idle_ttl(state)));
+        }
+        ClientMessage::SaveAsTemplate => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "save_as_template", "only the room owner can save a template")),
+                );
+            }
+            let template_id = Uuid::new_v4();
+            let snapshot = room.to_snapshot();
+            state.templates.write().await.insert(template_id, snapshot);
+            return (current_user, Some(ServerMessage::TemplateSaved { template_id }));
+        }
+        ClientMessage::RequestGuestLink => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "request_guest_link", "only the room owner can request a guest link")),
+                );
+            }
+            let url = format!(
+                "{}/join/{}?guest_token={}",
+                crate::http::public_base_url(),
+                room.join_code,
+                room.watcher_guest_token
+            );
+            return (current_user, Some(ServerMessage::GuestLink { url }));
+        }
+        ClientMessage::RequestSpectatorLink => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "request_spectator_link", "only the room owner can request a spectator link")),
+                );
+            }
+            let url = format!(
+                "{}/ws/{}/observe?spectator_token={}",
+                crate::http::public_base_url(),
+                room.id,
+                room.spectator_token
+            );
+            return (current_user, Some(ServerMessage::SpectatorLink { url }));
+        }
+        ClientMessage::ImportJira { query } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "import_jira", "only the room owner can import from JIRA")),
+                );
+            }
+            let Some(jira_config) = state.config.jira.clone() else {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Rejected, "import_jira", "this server has no JIRA integration configured")),
+                );
+            };
+            let state = state.clone();
+            tokio::spawn(async move {
+                match crate::jira::fetch(&jira_config, &query).await {
+                    Ok(issues) => {
+                        let mut rooms = state.rooms.write().await;
+                        let Some(room) = rooms.get_mut(&room_id) else { return };
+                        for issue in issues {
+                            let mut story = Story::new(issue.summary, String::new());
+                            story.set_story_url(Some(issue.url));
+                            room.stories.push(story);
+                        }
+                        broadcast_room_state(room);
+                    }
+                    Err(err) => {
+                        warn!(%err, %room_id, "jira import failed");
+                    }
+                }
+            });
+        }
+        ClientMessage::ImportGithub { repo, label, milestone } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "import_github", "only the room owner can import from GitHub")),
+                );
+            }
+            let Some(github_config) = state.config.github.clone() else {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Rejected, "import_github", "this server has no GitHub integration configured")),
+                );
+            };
+            let state = state.clone();
+            tokio::spawn(async move {
+                match crate::github::fetch(&github_config, &repo, label.as_deref(), milestone.as_deref()).await {
+                    Ok(issues) => {
+                        let mut rooms = state.rooms.write().await;
+                        let Some(room) = rooms.get_mut(&room_id) else { return };
+                        for issue in issues {
+                            let mut story = Story::new(issue.title, issue.description);
+                            story.set_story_url(Some(issue.url));
+                            room.stories.push(story);
+                        }
+                        broadcast_room_state(room);
+                    }
+                    Err(err) => {
+                        warn!(%err, %room_id, "github import failed");
+                    }
+                }
+            });
+        }
+        ClientMessage::MintApiToken { scopes } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "mint_api_token", "only the room owner can mint API tokens")),
+                );
+            }
+            let token = Uuid::new_v4().to_string();
+            room.api_tokens.insert(token.clone(), scopes.clone());
+            return (current_user, Some(ServerMessage::ApiTokenMinted { token, scopes }));
+        }
+        ClientMessage::RevokeApiToken { token } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "revoke_api_token", "only the room owner can revoke API tokens")),
+                );
+            }
+            room.api_tokens.remove(&token);
+        }
+        ClientMessage::SetChecklistItems { items } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "set_checklist_items", "only the room owner can set the checklist")),
+                );
+            }
+            room.config.checklist_items = items;
+            room.broadcast(room_config_message(room, room_idle_ttl(state)));
+        }
+        ClientMessage::SetChecklistItem { story_id, item, checked } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "set_checklist_item", "only the room owner can tick checklist items")),
+                );
+            }
+            if let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) {
+                story.checklist.insert(item, checked);
+                broadcast_story_update(room, story_id);
+            }
+        }
+        ClientMessage::SetStoryUrl { story_id, story_url } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "set_story_url", "only the room owner can set a story's external reference")),
+                );
+            }
+            if let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) {
+                story.set_story_url(story_url);
+                broadcast_story_update(room, story_id);
+            }
+        }
+        ClientMessage::EditStory { story_id, story_url, description } => {
+            if current_user != Some(room.owner_id) {
+                return (current_user, Some(error_reply(ErrorCode::Forbidden, "edit_story", "only the room owner can edit a story")));
+            }
+            if let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) {
+                if let Some(story_url) = story_url {
+                    story.set_story_url(Some(story_url));
+                }
+                if let Some(description) = description {
+                    story.set_description(description);
+                }
+                broadcast_story_update(room, story_id);
+            }
+        }
+        ClientMessage::SetStoryVoterScope { story_id, voter_ids } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "set_story_voter_scope", "only the room owner can scope a story to specific voters")),
+                );
+            }
+            if let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) {
+                if let Some(scope) = &voter_ids {
+                    story.votes.retain(|user_id, _| scope.contains(user_id));
+                }
+                story.voter_scope = voter_ids;
+                broadcast_story_update(room, story_id);
+            }
+        }
+        ClientMessage::Revote { story_id } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "revote", "only the room owner can restart voting on a story")),
+                );
+            }
+            if let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) {
+                story.votes.clear();
+                story.revealed = false;
+                story.phase = crate::room::StoryPhase::Voting;
+                broadcast_story_update(room, story_id);
+            }
+        }
+        ClientMessage::Skip { story_id } => {
+            if current_user != Some(room.owner_id) {
+                return (current_user, Some(error_reply(ErrorCode::Forbidden, "skip", "only the room owner can skip a story")));
+            }
+            if let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) {
+                story.phase = crate::room::StoryPhase::Skipped;
+                broadcast_story_update(room, story_id);
+            }
+        }
+        ClientMessage::SetFinalEstimate { story_id, value } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "set_final_estimate", "only the room owner can set a story's final estimate")),
+                );
+            }
+            if let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) {
+                story.final_estimate = value;
+                let story_title = story.title.clone();
+                let final_estimate = story.final_estimate.clone();
+                let issue_key = story.issue_key.clone();
+                let votes: Vec<(Uuid, String)> = story.votes.iter().map(|(id, vote)| (*id, vote.value.clone())).collect();
+                broadcast_story_update(room, story_id);
+                if let Some(estimate) = &final_estimate {
+                    let votes = votes
+                        .into_iter()
+                        .filter_map(|(id, value)| {
+                            room.users.get(&id).map(|user| crate::notify::VoteRecord { voter: user.name.clone(), value })
+                        })
+                        .collect();
+                    state.notifications.notify(crate::notify::NotificationEvent::StoryFinished {
+                        room_id,
+                        story_title,
+                        summary: estimate.clone(),
+                        votes,
+                        slack_webhook_override: room.config.slack_webhook_url.clone(),
+                    });
+                }
+                if room.config.jira_writeback {
+                    if let (Some(jira_config), Some(issue_key), Some(estimate)) = (state.config.jira.clone(), issue_key, final_estimate) {
+                        tokio::spawn(async move {
+                            if let Err(err) = crate::jira::write_estimate(&jira_config, &issue_key, &estimate).await {
+                                warn!(%err, %issue_key, "failed to write estimate back to jira");
+                            }
+                        });
+                    }
+                }
+            }
+        }
+        ClientMessage::SetStoryPhase { story_id, phase, timer_secs } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "set_story_phase", "only the room owner can move a story between phases")),
+                );
+            }
+            if let Some(story) = room.stories.iter_mut().find(|s| s.id == story_id) {
+                story.phase = phase;
+                story.phase_deadline = timer_secs.map(|secs| chrono::Utc::now() + chrono::Duration::seconds(secs as i64));
+                story.revealed = phase == crate::room::StoryPhase::Revealed;
+                let revealed = story.revealed;
+                broadcast_story_update(room, story_id);
+                if revealed {
+                    room.broadcast(ServerMessage::Notify { hint: crate::ws::NotificationHint::Revealed });
+                    broadcast_session_advisories(room);
+                }
+            }
+        }
+        ClientMessage::StartTimer { story_id, seconds } => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "start_timer", "only the room owner can start a story timer")),
+                );
+            }
+            if room.stories.iter().any(|s| s.id == story_id) {
+                tokio::spawn(run_story_timer(state.clone(), room_id, story_id, seconds));
+            }
+        }
+        ClientMessage::CloneRoom => {
+            if current_user != Some(room.owner_id) {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "clone_room", "only the room owner can clone the room")),
+                );
+            }
+            let mut snapshot = room.to_snapshot();
+            snapshot.stories.retain(|s| s.final_estimate.is_none());
+            let owner_id = room.owner_id;
+            let new_room = Room::from_snapshot(snapshot, owner_id);
+            let new_room_id = new_room.id;
+            let join_code = new_room.join_code.clone();
+            rooms.insert(new_room_id, new_room);
+            let state = state.clone();
+            tokio::spawn(async move { crate::http::register_with_cluster(&state, new_room_id).await });
+            return (current_user, Some(ServerMessage::RoomCloned { room_id: new_room_id, join_code }));
+        }
+        ClientMessage::ExportSnapshot => {
+            if current_user == Some(room.owner_id) {
+                return (current_user, Some(ServerMessage::Snapshot { snapshot: room.to_snapshot() }));
+            } else {
+                return (
+                    current_user,
+                    Some(error_reply(ErrorCode::Forbidden, "export_snapshot", "only the room owner can export a snapshot")),
+                );
+            }
+        }
+    }
+    (current_user, None)
+}
+
+/// Checks for pathological voting patterns across the whole session (see
+/// `stats::session_advisories`) and broadcasts them if any were found.
+/// Called after every reveal; a no-op when no pattern is detected, and
+/// the same flags are broadcast again after each reveal while they apply.
+fn broadcast_session_advisories(room: &mut crate::room::Room) {
+    let flags = crate::stats::session_advisories(&room.stories, &room.config.deck);
+    if !flags.is_empty() {
+        room.broadcast(ServerMessage::SessionAdvisories { flags });
+    }
+}
+
+/// Builds the room's handshake/config message, including the reconnect
+/// backoff guidance clients should use if this connection drops.
+fn room_config_message(room: &crate::room::Room, ttl: Duration) -> ServerMessage {
+    ServerMessage::RoomConfigMessage {
+        name: room.name.clone(),
+        config: room.config.clone(),
+        expires_at: room.expires_in(ttl).map(|d| chrono::Utc::now() + d),
+        reconnect: crate::ws::ReconnectPolicy::default(),
+    }
+}
+
+pub(crate) fn broadcast_room_state(room: &mut crate::room::Room) {
+    let users = room
+        .users
+        .values()
+        .filter(|u| user_is_visible(&room.config, u))
+        .cloned()
+        .collect();
+    let seq = room.bump_seq();
+    room.broadcast(ServerMessage::RoomState {
+        seq,
+        users,
+        stories: room.stories.iter().map(|s| s.view(&room.config, &room.users)).collect(),
+        current_story: room.current_story.and_then(|i| room.stories.get(i)).map(|s| s.id),
+        on_break: room.on_break,
+    });
+}
+
+/// Broadcasts just one story plus the current-story pointer, instead of
+/// the full `RoomState`. Used for per-story edits (reveal, reset,
+/// deadline, attachments, becoming active) so a large backlog doesn't get
+/// resent in full on every one of them; deck changes and new stories
+/// still go through `broadcast_room_state` since those touch the list
+/// itself.
+pub(crate) fn broadcast_story_update(room: &mut crate::room::Room, story_id: Uuid) {
+    let Some(story) = room.stories.iter().find(|s| s.id == story_id) else { return };
+    let story_view = story.view(&room.config, &room.users);
+    let current_story = room.current_story.and_then(|i| room.stories.get(i)).map(|s| s.id);
+    let seq = room.bump_seq();
+    room.broadcast(ServerMessage::StoryUpdate { seq, story: story_view, current_story });
+}
+
+/// Visible users, respecting `hide_watchers`, as of right now — snapshot
+/// this before a mutation to diff against the post-mutation state with
+/// `broadcast_users_delta`.
+pub(crate) fn visible_users(room: &crate::room::Room) -> std::collections::HashMap<Uuid, User> {
+    room.users
+        .iter()
+        .filter(|(_, u)| user_is_visible(&room.config, u))
+        .map(|(id, u)| (*id, u.clone()))
+        .collect()
+}
+
+/// Broadcasts only what changed in the user list since `before` was
+/// captured, instead of the full `RoomState`. Cheaper than a full
+/// broadcast in large rooms where stories didn't change at all.
+pub(crate) fn broadcast_users_delta(room: &mut crate::room::Room, before: std::collections::HashMap<Uuid, User>) {
+    let after = visible_users(room);
+    let added: Vec<User> = after.iter().filter(|(id, _)| !before.contains_key(id)).map(|(_, u)| u.clone()).collect();
+    let updated: Vec<User> = after
+        .iter()
+        .filter_map(|(id, u)| before.get(id).filter(|prev| *prev != u).map(|_| u.clone()))
+        .collect();
+    let removed: Vec<Uuid> = before.keys().filter(|id| !after.contains_key(id)).copied().collect();
+    if added.is_empty() && updated.is_empty() && removed.is_empty() {
+        return;
+    }
+    let seq = room.bump_seq();
+    room.broadcast(ServerMessage::UsersDelta { seq, added, updated, removed });
+}
@@ -0,0 +1,724 @@
+use std::collections::HashMap;
+
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap};
+use axum::response::IntoResponse;
+use axum::Json;
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::persistence::HistoryRow;
+use crate::room::{ApiTokenScope, Room, Story};
+use crate::schedule::{Recurrence, RecurringSchedule};
+use crate::snapshot::RoomSnapshot;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRoomRequest {
+    pub name: String,
+    /// Optional future session time. Pre-created rooms with this set are
+    /// exempt from idle GC until that time passes, so the join link can
+    /// be shared in advance of the actual session.
+    #[serde(default)]
+    pub scheduled_for: Option<chrono::DateTime<chrono::Utc>>,
+    /// Optional password. When set, joining (and observing) this room's
+    /// WebSocket requires the matching `?password=` query param — see
+    /// `Room::password`.
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Name of a `Config::room_templates` entry to seed the new room's
+    /// deck, auto-reveal delay, and stories from.
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateRoomResponse {
+    pub room_id: Uuid,
+    pub join_code: String,
+    pub owner_id: Uuid,
+}
+
+pub async fn create_room(
+    State(state): State<AppState>,
+    Json(req): Json<CreateRoomRequest>,
+) -> Result<Json<CreateRoomResponse>, crate::error::ApiError> {
+    let owner_id = Uuid::new_v4();
+    let mut room = Room::new(req.name, owner_id);
+    room.scheduled_for = req.scheduled_for;
+    room.password = req.password;
+    if let Some(deck) = &state.config.default_deck {
+        room.config.deck = deck.clone();
+    }
+    if let Some(template_name) = &req.template {
+        let template = state
+            .config
+            .room_templates
+            .get(template_name)
+            .ok_or_else(|| crate::error::ApiError::bad_request(format!("no such room template: {template_name}")))?;
+        if let Some(deck) = &template.deck {
+            room.config.deck = deck.clone();
+        }
+        room.config.auto_reveal_delay_secs = template.auto_reveal_delay_secs;
+        room.stories = template.stories.iter().map(|s| Story::new(s.title.clone(), s.description.clone())).collect();
+    }
+    let room_id = room.id;
+    let join_code = room.join_code.clone();
+    let name = room.name.clone();
+    let mut rooms = state.rooms.write().await;
+    if let Some(max_rooms) = state.config.max_rooms {
+        if rooms.len() >= max_rooms {
+            return Err(crate::error::ApiError::too_many_requests("this server is at its room limit, try again later"));
+        }
+    }
+    rooms.insert(room_id, room);
+    drop(rooms);
+    state.notifications.notify(crate::notify::NotificationEvent::RoomCreated { room_id, name });
+    register_with_cluster(&state, room_id).await;
+    Ok(Json(CreateRoomResponse { room_id, join_code, owner_id }))
+}
+
+/// Best-effort registration in the multi-instance room directory (see
+/// `cluster.rs`); a Redis hiccup shouldn't fail room creation, just leave
+/// the room only reachable on this instance.
+pub(crate) async fn register_with_cluster(state: &AppState, room_id: Uuid) {
+    if let Some(cluster) = &state.cluster {
+        if let Err(err) = cluster.register_room(room_id).await {
+            tracing::warn!(%err, %room_id, "failed to register room in cluster directory");
+        }
+    }
+}
+
+/// Base URL used to build the join link encoded into room QR codes.
+/// Overridable since the server rarely knows its own public hostname.
+pub(crate) fn public_base_url() -> String {
+    std::env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".to_string())
+}
+
+/// Renders a scannable join link for the room as an inline SVG QR code.
+pub async fn room_qr_code(
+    State(state): State<AppState>,
+    Path(room_id): Path<Uuid>,
+) -> Result<impl IntoResponse, crate::error::ApiError> {
+    let rooms = state.rooms.read().await;
+    let room = rooms.get(&room_id).ok_or_else(|| crate::error::ApiError::not_found("room not found"))?;
+    let join_url = format!("{}/join/{}", public_base_url(), room.join_code);
+    let code = QrCode::new(join_url).expect("join url is short enough to always fit a QR code");
+    let svg = code.render::<svg::Color>().min_dimensions(256, 256).build();
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg))
+}
+
+/// Looks up the scopes granted to a per-room API token presented via the
+/// `X-Room-Token` header. Used by the token-authenticated endpoints
+/// below instead of the instance-wide `ADMIN_TOKEN`.
+fn room_token_scopes<'a>(room: &'a Room, headers: &HeaderMap) -> Result<&'a Vec<ApiTokenScope>, crate::error::ApiError> {
+    let token = headers
+        .get("x-room-token")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| crate::error::ApiError::forbidden("missing X-Room-Token header"))?;
+    room.api_tokens.get(token).ok_or_else(|| crate::error::ApiError::forbidden("invalid room API token"))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddStoryViaTokenRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Adds a story using a per-room API token scoped to `AddStories`,
+/// instead of the instance-wide admin token — see `MintApiToken`.
+pub async fn add_story_via_token(
+    State(state): State<AppState>,
+    Path(room_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<AddStoryViaTokenRequest>,
+) -> Result<(), crate::error::ApiError> {
+    let mut rooms = state.rooms.write().await;
+    let room = rooms.get_mut(&room_id).ok_or_else(|| crate::error::ApiError::not_found("room not found"))?;
+    if !room_token_scopes(room, &headers)?.contains(&ApiTokenScope::AddStories) {
+        return Err(crate::error::ApiError::forbidden("token is not scoped for adding stories"));
+    }
+    room.stories.push(Story::new(req.title, req.description));
+    crate::handler::broadcast_room_state(room);
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateStoryViaTokenRequest {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Updates a story's title/description using a per-room API token scoped
+/// to `AddStories`, the same scope that covers creating one.
+pub async fn update_story_via_token(
+    State(state): State<AppState>,
+    Path((room_id, story_id)): Path<(Uuid, Uuid)>,
+    headers: HeaderMap,
+    Json(req): Json<UpdateStoryViaTokenRequest>,
+) -> Result<(), crate::error::ApiError> {
+    let mut rooms = state.rooms.write().await;
+    let room = rooms.get_mut(&room_id).ok_or_else(|| crate::error::ApiError::not_found("room not found"))?;
+    if !room_token_scopes(room, &headers)?.contains(&ApiTokenScope::AddStories) {
+        return Err(crate::error::ApiError::forbidden("token is not scoped for managing stories"));
+    }
+    let story =
+        room.stories.iter_mut().find(|s| s.id == story_id).ok_or_else(|| crate::error::ApiError::not_found("no such story"))?;
+    if let Some(title) = req.title {
+        story.title = title;
+    }
+    if let Some(description) = req.description {
+        story.set_description(description);
+    }
+    crate::handler::broadcast_room_state(room);
+    Ok(())
+}
+
+/// Deletes a story using a per-room API token scoped to `AddStories`.
+pub async fn delete_story_via_token(
+    State(state): State<AppState>,
+    Path((room_id, story_id)): Path<(Uuid, Uuid)>,
+    headers: HeaderMap,
+) -> Result<(), crate::error::ApiError> {
+    let mut rooms = state.rooms.write().await;
+    let room = rooms.get_mut(&room_id).ok_or_else(|| crate::error::ApiError::not_found("room not found"))?;
+    if !room_token_scopes(room, &headers)?.contains(&ApiTokenScope::AddStories) {
+        return Err(crate::error::ApiError::forbidden("token is not scoped for managing stories"));
+    }
+    let before_len = room.stories.len();
+    room.stories.retain(|s| s.id != story_id);
+    if room.stories.len() == before_len {
+        return Err(crate::error::ApiError::not_found("no such story"));
+    }
+    if room.current_story.is_some_and(|i| i >= room.stories.len()) {
+        room.current_story = None;
+    }
+    crate::handler::broadcast_room_state(room);
+    Ok(())
+}
+
+/// A single row's outcome from `import_stories_via_token`, keyed by its
+/// 1-based position in the uploaded CSV so callers can match failures
+/// back to the file they sent.
+#[derive(Debug, Serialize)]
+pub struct ImportStoriesRowError {
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportStoriesResponse {
+    pub imported: usize,
+    pub errors: Vec<ImportStoriesRowError>,
+}
+
+/// Bulk-imports stories from a `url,description` CSV (the first row is
+/// always taken as a header and skipped) using a per-room API token
+/// scoped to `AddStories`, so a
+/// backlog exported from another tool can be loaded in one request.
+/// Unlike `add_story_via_token`, a bad row doesn't fail the whole
+/// request — it's recorded in `ImportStoriesResponse::errors` and the
+/// rest of the file is still imported.
+pub async fn import_stories_via_token(
+    State(state): State<AppState>,
+    Path(room_id): Path<Uuid>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ImportStoriesResponse>, crate::error::ApiError> {
+    let mut rooms = state.rooms.write().await;
+    let room = rooms.get_mut(&room_id).ok_or_else(|| crate::error::ApiError::not_found("room not found"))?;
+    if !room_token_scopes(room, &headers)?.contains(&ApiTokenScope::AddStories) {
+        return Err(crate::error::ApiError::forbidden("token is not scoped for adding stories"));
+    }
+    let mut reader = csv::ReaderBuilder::new().flexible(false).from_reader(body.as_bytes());
+    let mut imported = 0;
+    let mut errors = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let row = index + 1;
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                errors.push(ImportStoriesRowError { row, message: err.to_string() });
+                continue;
+            }
+        };
+        let (Some(url), Some(description)) = (record.get(0), record.get(1)) else {
+            errors.push(ImportStoriesRowError { row, message: "each row needs url, description columns".to_string() });
+            continue;
+        };
+        if url.trim().is_empty() {
+            errors.push(ImportStoriesRowError { row, message: "url column is empty".to_string() });
+            continue;
+        }
+        let title = crate::issue_key::extract(url).unwrap_or_else(|| url.to_string());
+        let mut story = Story::new(title, String::new());
+        story.set_description(description.to_string());
+        story.set_story_url(Some(url.to_string()));
+        room.stories.push(story);
+        imported += 1;
+    }
+    if imported > 0 {
+        crate::handler::broadcast_room_state(room);
+    }
+    Ok(Json(ImportStoriesResponse { imported, errors }))
+}
+
+/// Reads story results using a per-room API token scoped to
+/// `ReadResults`, instead of the instance-wide admin token.
+pub async fn room_results_via_token(
+    State(state): State<AppState>,
+    Path(room_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<crate::room::StoryView>>, crate::error::ApiError> {
+    let rooms = state.rooms.read().await;
+    let room = rooms.get(&room_id).ok_or_else(|| crate::error::ApiError::not_found("room not found"))?;
+    if !room_token_scopes(room, &headers)?.contains(&ApiTokenScope::ReadResults) {
+        return Err(crate::error::ApiError::forbidden("token is not scoped for reading results"));
+    }
+    Ok(Json(room.stories.iter().map(|s| s.view(&room.config, &room.users)).collect()))
+}
+
+/// Renders one row per story (URL, description, state, every
+/// participant's vote, and the final/average estimate) as CSV, for
+/// facilitators who just want to paste the session's results into a
+/// spreadsheet. Gated the same way as `room_results_via_token`.
+pub async fn export_results_csv(
+    State(state): State<AppState>,
+    Path(room_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, crate::error::ApiError> {
+    let rooms = state.rooms.read().await;
+    let room = rooms.get(&room_id).ok_or_else(|| crate::error::ApiError::not_found("room not found"))?;
+    if !room_token_scopes(room, &headers)?.contains(&ApiTokenScope::ReadResults) {
+        return Err(crate::error::ApiError::forbidden("token is not scoped for reading results"));
+    }
+    let mut voter_names: Vec<&str> = room.users.values().map(|u| u.name.as_str()).collect();
+    voter_names.sort_unstable();
+    voter_names.dedup();
+
+    let mut writer = csv::WriterBuilder::new().from_writer(Vec::new());
+    let mut header = vec!["title".to_string(), "url".to_string(), "description".to_string(), "state".to_string()];
+    header.extend(voter_names.iter().map(|name| name.to_string()));
+    header.push("final_estimate".to_string());
+    header.push("average_estimate".to_string());
+    writer.write_record(&header).expect("writing to an in-memory buffer cannot fail");
+
+    for story in &room.stories {
+        let state_label = if story.phase == crate::room::StoryPhase::Skipped {
+            "skipped"
+        } else if story.revealed {
+            "revealed"
+        } else {
+            "voting"
+        };
+        let votes_by_name: HashMap<&str, &str> = story
+            .votes
+            .iter()
+            .filter_map(|(user_id, vote)| room.users.get(user_id).map(|user| (user.name.as_str(), vote.value.as_str())))
+            .collect();
+        let stats = story.revealed.then(|| crate::stats::compute(story, &room.config, &room.users));
+        let mut record = vec![
+            story.title.clone(),
+            story.story_url.clone().unwrap_or_default(),
+            story.description.clone(),
+            state_label.to_string(),
+        ];
+        for name in &voter_names {
+            record.push(votes_by_name.get(name).copied().unwrap_or("").to_string());
+        }
+        record.push(story.final_estimate.clone().unwrap_or_default());
+        record.push(
+            stats
+                .and_then(|s| s.average)
+                .map(|v| crate::stats::format_number_for_locale(v, room.config.locale.as_deref()))
+                .unwrap_or_default(),
+        );
+        writer.write_record(&record).expect("writing to an in-memory buffer cannot fail");
+    }
+    let csv = writer.into_inner().expect("in-memory buffer flush cannot fail");
+    Ok(([(header::CONTENT_TYPE, "text/csv")], csv))
+}
+
+/// Renders a Markdown session summary (story list with estimates,
+/// per-story vote breakdown, participants, and timing) meant to be
+/// pasted directly into sprint notes. Gated the same way as
+/// `room_results_via_token`.
+pub async fn export_summary_markdown(
+    State(state): State<AppState>,
+    Path(room_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, crate::error::ApiError> {
+    let rooms = state.rooms.read().await;
+    let room = rooms.get(&room_id).ok_or_else(|| crate::error::ApiError::not_found("room not found"))?;
+    if !room_token_scopes(room, &headers)?.contains(&ApiTokenScope::ReadResults) {
+        return Err(crate::error::ApiError::forbidden("token is not scoped for reading results"));
+    }
+
+    let mut out = format!("# {}\n\n", room.name);
+    out.push_str(&format!("Session started: {}\n\n", room.created_at.to_rfc3339()));
+
+    let mut participants: Vec<&str> = room.users.values().map(|u| u.name.as_str()).collect();
+    participants.sort_unstable();
+    out.push_str("## Participants\n\n");
+    if participants.is_empty() {
+        out.push_str("_none_\n\n");
+    } else {
+        for name in &participants {
+            out.push_str(&format!("- {name}\n"));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("## Stories\n\n");
+    for story in &room.stories {
+        out.push_str(&format!("### {}\n\n", story.title));
+        if !story.description.is_empty() {
+            out.push_str(&format!("{}\n\n", story.description));
+        }
+        if story.phase == crate::room::StoryPhase::Skipped {
+            out.push_str("_Skipped — out of scope._\n\n");
+            continue;
+        }
+        let estimate = story.final_estimate.as_deref().unwrap_or("unestimated");
+        out.push_str(&format!("Final estimate: **{estimate}**\n\n"));
+        if story.votes.is_empty() {
+            out.push_str("No votes cast.\n\n");
+        } else {
+            out.push_str("| Voter | Vote |\n|---|---|\n");
+            let mut votes: Vec<(&str, &str)> = story
+                .votes
+                .iter()
+                .filter_map(|(user_id, vote)| room.users.get(user_id).map(|user| (user.name.as_str(), vote.value.as_str())))
+                .collect();
+            votes.sort_unstable();
+            for (name, value) in votes {
+                out.push_str(&format!("| {name} | {value} |\n"));
+            }
+            out.push('\n');
+        }
+    }
+
+    Ok(([(header::CONTENT_TYPE, "text/markdown")], out))
+}
+
+/// Full room state as returned by `room_state_via_token`, for scripts
+/// managing a session's lifecycle over REST instead of a WebSocket.
+#[derive(Debug, Serialize)]
+pub struct RoomStateResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub join_code: String,
+    pub config: crate::room::RoomConfig,
+    pub stories: Vec<crate::room::StoryView>,
+    pub current_story: Option<Uuid>,
+    pub on_break: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Reads a room's full state using a per-room API token scoped to
+/// `ManageRoom` — the REST counterpart to `ClientMessage::ExportSnapshot`
+/// for scripts that never open a WebSocket.
+pub async fn room_state_via_token(
+    State(state): State<AppState>,
+    Path(room_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<RoomStateResponse>, crate::error::ApiError> {
+    let rooms = state.rooms.read().await;
+    let room = rooms.get(&room_id).ok_or_else(|| crate::error::ApiError::not_found("room not found"))?;
+    if !room_token_scopes(room, &headers)?.contains(&ApiTokenScope::ManageRoom) {
+        return Err(crate::error::ApiError::forbidden("token is not scoped for managing the room"));
+    }
+    Ok(Json(RoomStateResponse {
+        id: room.id,
+        name: room.name.clone(),
+        join_code: room.join_code.clone(),
+        config: room.config.clone(),
+        stories: room.stories.iter().map(|s| s.view(&room.config, &room.users)).collect(),
+        current_story: room.current_story.and_then(|i| room.stories.get(i)).map(|s| s.id),
+        on_break: room.on_break,
+        created_at: room.created_at,
+    }))
+}
+
+/// Exports a single room's settings and stories (their durable fields,
+/// see `Room::to_snapshot`) as a `RoomSnapshot`, the shape
+/// `POST /api/rooms/import` takes under its `snapshot` key. Votes and
+/// connected users are not part of the export. Gated on `ManageRoom`
+/// since a snapshot includes unpublished stories, not just published
+/// results.
+pub async fn export_room_json(
+    State(state): State<AppState>,
+    Path(room_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<RoomSnapshot>, crate::error::ApiError> {
+    let rooms = state.rooms.read().await;
+    let room = rooms.get(&room_id).ok_or_else(|| crate::error::ApiError::not_found("room not found"))?;
+    if !room_token_scopes(room, &headers)?.contains(&ApiTokenScope::ManageRoom) {
+        return Err(crate::error::ApiError::forbidden("token is not scoped for managing the room"));
+    }
+    Ok(Json(room.to_snapshot()))
+}
+
+/// Deletes a room using a per-room API token scoped to `ManageRoom`,
+/// closing out every connected client the same way the idle GC sweep
+/// does.
+pub async fn delete_room_via_token(
+    State(state): State<AppState>,
+    Path(room_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<(), crate::error::ApiError> {
+    let mut rooms = state.rooms.write().await;
+    let room = rooms.get_mut(&room_id).ok_or_else(|| crate::error::ApiError::not_found("room not found"))?;
+    if !room_token_scopes(room, &headers)?.contains(&ApiTokenScope::ManageRoom) {
+        return Err(crate::error::ApiError::forbidden("token is not scoped for managing the room"));
+    }
+    room.broadcast(crate::ws::ServerMessage::RoomClosing { reason: crate::ws::CloseReason::RoomClosed });
+    rooms.remove(&room_id);
+    drop(rooms);
+    state.notifications.notify(crate::notify::NotificationEvent::SessionEnded { room_id });
+    if let Some(cluster) = &state.cluster {
+        let _ = cluster.unregister_room(room_id).await;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolveJoinCodeResponse {
+    pub room_id: Uuid,
+}
+
+/// Resolves a short join code (as printed on an invite or read aloud) to
+/// the room's full id. Codes aren't indexed separately since a process
+/// is expected to hold at most a few thousand live rooms at once.
+pub async fn resolve_join_code(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+) -> Result<Json<ResolveJoinCodeResponse>, crate::error::ApiError> {
+    let code = code.to_uppercase();
+    let rooms = state.rooms.read().await;
+    let room = rooms
+        .values()
+        .find(|r| r.join_code == code)
+        .ok_or_else(|| crate::error::ApiError::not_found("no room with that join code"))?;
+    Ok(Json(ResolveJoinCodeResponse { room_id: room.id }))
+}
+
+/// Shared secret operators pass in the `X-Admin-Token` header to prove
+/// they're allowed to broadcast instance-wide announcements.
+pub(crate) fn is_admin(headers: &HeaderMap) -> bool {
+    let Ok(expected) = std::env::var("ADMIN_TOKEN") else { return false };
+    headers.get("x-admin-token").and_then(|v| v.to_str().ok()) == Some(expected.as_str())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnnounceRequest {
+    pub message: String,
+}
+
+/// Broadcasts a message to every live room at once (a MOTD, a warning
+/// about upcoming maintenance, etc.). Requires the `ADMIN_TOKEN` env var
+/// to be set and matched via `X-Admin-Token`.
+pub async fn announce(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<AnnounceRequest>,
+) -> Result<(), crate::error::ApiError> {
+    if !is_admin(&headers) {
+        return Err(crate::error::ApiError::forbidden("missing or invalid admin token"));
+    }
+    let rooms = state.rooms.read().await;
+    for room in rooms.values() {
+        room.broadcast(crate::ws::ServerMessage::Announcement { message: req.message.clone() });
+    }
+    Ok(())
+}
+
+/// Instantiates a new room from a previously saved template (see
+/// `ClientMessage::SaveAsTemplate`), the same way `import_room` does for
+/// a raw snapshot.
+pub async fn create_room_from_template(
+    State(state): State<AppState>,
+    Path(template_id): Path<Uuid>,
+) -> Result<Json<CreateRoomResponse>, crate::error::ApiError> {
+    let snapshot = state
+        .templates
+        .read()
+        .await
+        .get(&template_id)
+        .cloned()
+        .ok_or_else(|| crate::error::ApiError::not_found("no such template"))?;
+    let owner_id = Uuid::new_v4();
+    let room = Room::from_snapshot(snapshot, owner_id);
+    let room_id = room.id;
+    let join_code = room.join_code.clone();
+    state.rooms.write().await.insert(room_id, room);
+    register_with_cluster(&state, room_id).await;
+    Ok(Json(CreateRoomResponse { room_id, join_code, owner_id }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateScheduleRequest {
+    pub room_name: String,
+    pub recurrence: Recurrence,
+    pub first_run: chrono::DateTime<chrono::Utc>,
+}
+
+/// Registers a recurring schedule; the background task in `schedule.rs`
+/// pre-creates a fresh room each time `next_run` is reached.
+pub async fn create_schedule(
+    State(state): State<AppState>,
+    Json(req): Json<CreateScheduleRequest>,
+) -> Json<RecurringSchedule> {
+    let schedule = RecurringSchedule {
+        id: Uuid::new_v4(),
+        room_name: req.room_name,
+        recurrence: req.recurrence,
+        next_run: req.first_run,
+    };
+    state.schedules.write().await.push(schedule.clone());
+    Json(schedule)
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstanceStats {
+    pub room_count: usize,
+    pub persistent_room_count: usize,
+    pub connected_user_count: usize,
+    pub story_count: usize,
+}
+
+/// Operator-facing instance-wide counters, gated the same way as
+/// `announce` since both are only meant for whoever runs the server.
+pub async fn instance_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<InstanceStats>, crate::error::ApiError> {
+    if !is_admin(&headers) {
+        return Err(crate::error::ApiError::forbidden("missing or invalid admin token"));
+    }
+    let rooms = state.rooms.read().await;
+    let stats = InstanceStats {
+        room_count: rooms.len(),
+        persistent_room_count: rooms.values().filter(|r| r.persistent).count(),
+        connected_user_count: rooms.values().map(|r| r.users.len()).sum(),
+        story_count: rooms.values().map(|r| r.stories.len()).sum(),
+    };
+    Ok(Json(stats))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HandoffRequest {
+    /// Base URL of the instance clients should reconnect to.
+    pub target_url: String,
+}
+
+/// Hands a room off to another instance: clients are told where to
+/// reconnect and are handed the room's snapshot directly in the same
+/// message, so a new room can be recreated there with no gap where the
+/// room exists nowhere. The room is then dropped from this process.
+///
+/// This only moves state that's already snapshot-able (see
+/// `Room::to_snapshot`) — in-progress votes and who's currently
+/// connected don't survive the hop, the same as any other export.
+pub async fn handoff_room(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(room_id): Path<Uuid>,
+    Json(req): Json<HandoffRequest>,
+) -> Result<(), crate::error::ApiError> {
+    if !is_admin(&headers) {
+        return Err(crate::error::ApiError::forbidden("missing or invalid admin token"));
+    }
+    let mut rooms = state.rooms.write().await;
+    let room = rooms.get(&room_id).ok_or_else(|| crate::error::ApiError::not_found("room not found"))?;
+    room.broadcast(crate::ws::ServerMessage::Migrate {
+        target_url: req.target_url,
+        snapshot: room.to_snapshot(),
+    });
+    rooms.remove(&room_id);
+    drop(rooms);
+    if let Some(cluster) = &state.cluster {
+        let _ = cluster.unregister_room(room_id).await;
+    }
+    Ok(())
+}
+
+/// Dumps every live room as a snapshot, for a full-instance backup.
+/// Gated the same way as the other operator-only endpoints.
+pub async fn export_all_rooms(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<HashMap<Uuid, RoomSnapshot>>, crate::error::ApiError> {
+    if !is_admin(&headers) {
+        return Err(crate::error::ApiError::forbidden("missing or invalid admin token"));
+    }
+    let rooms = state.rooms.read().await;
+    Ok(Json(rooms.iter().map(|(id, room)| (*id, room.to_snapshot())).collect()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportRoomRequest {
+    pub snapshot: RoomSnapshot,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportHistoryResponse {
+    pub imported: usize,
+}
+
+/// Bulk-imports historical estimation data (`story,estimate,actual,date`
+/// CSV columns; the first row is always taken as a header and skipped)
+/// into the persistence layer, so
+/// teams adopting the tool can seed velocity/calibration analytics with
+/// past sprints. Requires the SQLite persistence backend to be enabled.
+pub async fn import_estimation_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ImportHistoryResponse>, crate::error::ApiError> {
+    if !is_admin(&headers) {
+        return Err(crate::error::ApiError::forbidden("missing or invalid admin token"));
+    }
+    let Some(store) = &state.persistence else {
+        return Err(crate::error::ApiError::bad_request("no persistence backend is configured to import into"));
+    };
+    let mut reader = csv::ReaderBuilder::new().flexible(false).from_reader(body.as_bytes());
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|err| crate::error::ApiError::bad_request(format!("invalid CSV row: {err}")))?;
+        let (Some(story), Some(estimate), Some(actual), Some(date)) =
+            (record.get(0), record.get(1), record.get(2), record.get(3))
+        else {
+            return Err(crate::error::ApiError::bad_request("each row needs story, estimate, actual, date columns"));
+        };
+        rows.push(HistoryRow {
+            story: story.to_string(),
+            estimate: estimate.to_string(),
+            actual: actual.to_string(),
+            date: date.to_string(),
+        });
+    }
+    store.insert_history(&rows);
+    Ok(Json(ImportHistoryResponse { imported: rows.len() }))
+}
+
+/// Instantiates a brand-new room from a previously exported snapshot.
+/// Fresh room/owner/story ids are always generated, so importing the
+/// same snapshot twice produces two independent rooms, not a conflict.
+pub async fn import_room(
+    State(state): State<AppState>,
+    Json(req): Json<ImportRoomRequest>,
+) -> Json<CreateRoomResponse> {
+    let owner_id = Uuid::new_v4();
+    let room = Room::from_snapshot(req.snapshot, owner_id);
+    let room_id = room.id;
+    let join_code = room.join_code.clone();
+    state.rooms.write().await.insert(room_id, room);
+    register_with_cluster(&state, room_id).await;
+    Json(CreateRoomResponse { room_id, join_code, owner_id })
+}
@@ -0,0 +1,54 @@
+//! Best-effort extraction of a short issue key from a story's external
+//! tracker URL (see `Story::set_story_url`), so dedup, exports, and
+//! estimate write-back can key off `Story::issue_key` instead of each
+//! re-parsing `story_url` itself.
+
+/// Tries to pull an issue key out of `url` for the trackers this server
+/// recognizes. Returns `None` for anything else — an unrecognized URL is
+/// kept verbatim in `story_url` but just has no key.
+pub fn extract(url: &str) -> Option<String> {
+    let url = url.trim();
+    extract_jira(url).or_else(|| extract_github(url)).or_else(|| extract_gitlab(url))
+}
+
+/// `https://<site>.atlassian.net/browse/PROJ-123` -> `PROJ-123`.
+fn extract_jira(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("/browse/")?;
+    let key = rest.split(['/', '?', '#']).next()?;
+    is_jira_key(key).then(|| key.to_string())
+}
+
+/// Whether `key` looks like a JIRA issue key (`PROJ-123`) as opposed to a
+/// GitHub/GitLab `owner/repo#n`-style key — used by `jira::write_estimate`
+/// to skip stories whose `issue_key` came from a different tracker.
+pub(crate) fn is_jira_key(key: &str) -> bool {
+    let Some((project, number)) = key.split_once('-') else { return false };
+    !project.is_empty()
+        && project.chars().all(|c| c.is_ascii_alphabetic())
+        && !number.is_empty()
+        && number.chars().all(|c| c.is_ascii_digit())
+}
+
+/// `https://github.com/<owner>/<repo>/issues/<n>` (or `/pull/<n>`) ->
+/// `owner/repo#n`.
+fn extract_github(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("github.com/")?;
+    let mut parts = rest.split('/');
+    let owner = parts.next()?;
+    let repo = parts.next()?;
+    let kind = parts.next()?;
+    if kind != "issues" && kind != "pull" {
+        return None;
+    }
+    let number = parts.next()?.split(['/', '?', '#']).next()?;
+    number.chars().all(|c| c.is_ascii_digit()).then(|| format!("{owner}/{repo}#{number}"))
+}
+
+/// `https://gitlab.com/<group>/<project>/-/issues/<n>` ->
+/// `group/project#n`.
+fn extract_gitlab(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("gitlab.com/")?;
+    let (project_path, tail) = rest.split_once("/-/issues/")?;
+    let number = tail.split(['/', '?', '#']).next()?;
+    number.chars().all(|c| c.is_ascii_digit()).then(|| format!("{project_path}#{number}"))
+}
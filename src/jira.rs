@@ -0,0 +1,88 @@
+use serde::Deserialize;
+
+use crate::config::JiraConfig;
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    issues: Vec<Issue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    key: String,
+    fields: IssueFields,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueFields {
+    summary: String,
+}
+
+/// One issue fetched from JIRA, ready to become a `Story`.
+pub struct FetchedIssue {
+    pub summary: String,
+    pub url: String,
+}
+
+/// Fetches issues matching `query` — a JQL expression, or a
+/// comma-separated list of issue keys — from the configured JIRA
+/// instance, for `ClientMessage::ImportJira`.
+pub async fn fetch(config: &JiraConfig, query: &str) -> Result<Vec<FetchedIssue>, String> {
+    let jql = if looks_like_jql(query) {
+        query.to_string()
+    } else {
+        let keys: Vec<&str> = query.split(',').map(str::trim).filter(|key| !key.is_empty()).collect();
+        format!("key in ({})", keys.join(","))
+    };
+    let base_url = config.base_url.trim_end_matches('/');
+    let response = reqwest::Client::new()
+        .get(format!("{base_url}/rest/api/2/search"))
+        .bearer_auth(&config.api_token)
+        .query(&[("jql", jql.as_str())])
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("jira returned {}", response.status()));
+    }
+    let parsed: SearchResponse = response.json().await.map_err(|err| err.to_string())?;
+    Ok(parsed
+        .issues
+        .into_iter()
+        .map(|issue| FetchedIssue {
+            summary: format!("{}: {}", issue.key, issue.fields.summary),
+            url: format!("{base_url}/browse/{}", issue.key),
+        })
+        .collect())
+}
+
+/// Heuristic: a bare comma-separated list of issue keys has no spaces;
+/// anything else is passed through to JIRA as a JQL expression verbatim.
+fn looks_like_jql(query: &str) -> bool {
+    query.contains(' ')
+}
+
+/// Writes `estimate` back to `issue_key`'s story-points field (see
+/// `JiraConfig::story_points_field`), for
+/// `ClientMessage::SetFinalEstimate` on a room with
+/// `RoomConfig::jira_writeback` enabled. Non-numeric estimates (e.g. "?")
+/// are skipped since a story-points field can't hold them.
+pub async fn write_estimate(config: &JiraConfig, issue_key: &str, estimate: &str) -> Result<(), String> {
+    if !crate::issue_key::is_jira_key(issue_key) {
+        return Err(format!("{issue_key} is not a JIRA issue key"));
+    }
+    let points: f64 = estimate.parse().map_err(|_| format!("estimate {estimate:?} is not numeric"))?;
+    let base_url = config.base_url.trim_end_matches('/');
+    let body = serde_json::json!({ "fields": { config.story_points_field.clone(): points } });
+    let response = reqwest::Client::new()
+        .put(format!("{base_url}/rest/api/2/issue/{issue_key}"))
+        .bearer_auth(&config.api_token)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("jira returned {}", response.status()));
+    }
+    Ok(())
+}
@@ -0,0 +1,140 @@
+mod chaos;
+mod cleanup;
+#[cfg(feature = "client")]
+mod client;
+mod cluster;
+mod config;
+mod error;
+mod github;
+mod handler;
+mod http;
+mod issue_key;
+mod jira;
+mod markdown;
+mod metrics;
+mod notify;
+mod pagefetch;
+mod persistence;
+mod reminders;
+mod room;
+mod schedule;
+mod selfcheck;
+mod sim;
+mod snapshot;
+mod snapshot_file;
+mod state;
+mod stats;
+mod ws;
+
+use axum::routing::{get, post, put};
+use axum::Router;
+use tracing::warn;
+
+use config::Config;
+use state::AppState;
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let config_path = std::env::var("POKER_CONFIG").unwrap_or_else(|_| "config.toml".to_string());
+    let report = Config::load(&config_path);
+
+    if std::env::args().any(|arg| arg == "--check") {
+        let ok = selfcheck::run(&report).await;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
+    if let Some(arg) = std::env::args().find(|arg| arg == "--simulate" || arg.starts_with("--simulate=")) {
+        let iterations = arg.strip_prefix("--simulate=").and_then(|n| n.parse().ok()).unwrap_or(0);
+        let ok = sim::run(iterations);
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
+    for issue in &report.issues {
+        warn!(field = %issue.field, "{}", issue.message);
+    }
+    let listen_addr = report.config.listen_addr.clone().unwrap_or_else(|| "0.0.0.0:8080".to_string());
+
+    let mut state = AppState::new();
+    state.config = std::sync::Arc::new(report.config.clone());
+    let mut notifiers: Vec<std::sync::Arc<dyn notify::Notifier>> = report
+        .config
+        .webhooks
+        .iter()
+        .cloned()
+        .map(|webhook| std::sync::Arc::new(notify::WebhookNotifier::new(webhook)) as std::sync::Arc<dyn notify::Notifier>)
+        .collect();
+    notifiers.push(std::sync::Arc::new(notify::SlackNotifier::new(report.config.slack_webhook_url.clone())));
+    state.notifications = std::sync::Arc::new(notify::NotificationQueue::start(notifiers));
+    if let Some(path) = &report.config.sqlite_path {
+        match persistence::Store::open(path) {
+            Ok(store) => {
+                let store = std::sync::Arc::new(store);
+                persistence::restore(&state, &store).await;
+                state.persistence = Some(store.clone());
+                tokio::spawn(persistence::run(state.clone(), store));
+            }
+            Err(err) => warn!(%err, path, "failed to open sqlite persistence store, continuing without it"),
+        }
+    } else if let Some(path) = &report.config.snapshot_path {
+        snapshot_file::restore(&state, path).await;
+        let interval_secs = report.config.snapshot_interval_secs.unwrap_or(60);
+        let path = path.clone();
+        tokio::spawn(snapshot_file::run(state.clone(), path, std::time::Duration::from_secs(interval_secs.max(1))));
+    }
+    if let Some(redis_url) = &report.config.redis_url {
+        let self_url = std::env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| listen_addr.clone());
+        match cluster::ClusterDirectory::new(redis_url, self_url) {
+            Ok(directory) => state.cluster = Some(std::sync::Arc::new(directory)),
+            Err(err) => warn!(%err, redis_url, "failed to set up cluster directory, continuing single-instance"),
+        }
+    }
+
+    tokio::spawn(cleanup::run(state.clone()));
+    tokio::spawn(schedule::run(state.clone()));
+    tokio::spawn(reminders::run(state.clone()));
+
+    let app = Router::new()
+        .route("/api/rooms", post(http::create_room))
+        .route("/api/rooms/import", post(http::import_room))
+        .route("/api/rooms/by-code/:code", get(http::resolve_join_code))
+        .route("/api/rooms/:room_id", get(http::room_state_via_token).delete(http::delete_room_via_token))
+        .route("/api/rooms/:room_id/qr.svg", get(http::room_qr_code))
+        .route("/api/rooms/:room_id/stories", post(http::add_story_via_token).get(http::room_results_via_token))
+        .route(
+            "/api/rooms/:room_id/stories/:story_id",
+            put(http::update_story_via_token).delete(http::delete_story_via_token),
+        )
+        .route("/api/rooms/:room_id/stories/import", post(http::import_stories_via_token))
+        .route("/api/rooms/:room_id/results", get(http::room_results_via_token))
+        .route("/api/rooms/:room_id/export.csv", get(http::export_results_csv))
+        .route("/api/rooms/:room_id/export.md", get(http::export_summary_markdown))
+        .route("/api/rooms/:room_id/export.json", get(http::export_room_json))
+        .route("/api/admin/announce", post(http::announce))
+        .route("/api/admin/stats", get(http::instance_stats))
+        .route("/api/admin/metrics", get(metrics::export))
+        .route("/api/admin/rooms/:room_id/handoff", post(http::handoff_room))
+        .route("/api/admin/rooms/export", get(http::export_all_rooms))
+        .route("/api/admin/analytics/import", post(http::import_estimation_history))
+        .route("/api/schedules", post(http::create_schedule))
+        .route("/api/templates/:template_id/rooms", post(http::create_room_from_template))
+        .route("/ws/:room_id", get(handler::ws_route))
+        .route("/ws/:room_id/observe", get(handler::observe_route))
+        .with_state(state.clone());
+
+    let listener = tokio::net::TcpListener::bind(&listen_addr).await.unwrap();
+    axum::serve(listener, app).with_graceful_shutdown(shutdown_signal(state)).await.unwrap();
+}
+
+/// Waits for Ctrl+C, then tells every connected client why it's about to
+/// see its socket drop instead of leaving them to guess from a bare
+/// disconnect.
+async fn shutdown_signal(state: AppState) {
+    let _ = tokio::signal::ctrl_c().await;
+    warn!("shutting down, notifying connected clients");
+    let rooms = state.rooms.read().await;
+    for room in rooms.values() {
+        room.broadcast(ws::ServerMessage::RoomClosing { reason: ws::CloseReason::ServerShutdown });
+    }
+}
@@ -0,0 +1,11 @@
+use pulldown_cmark::{html, Options, Parser};
+
+/// Renders story Markdown to sanitized HTML. Sanitization happens after
+/// rendering (not by restricting the Markdown input) so every client
+/// gets identical, safe HTML regardless of what wrote the raw text.
+pub fn render(raw: &str) -> String {
+    let parser = Parser::new_ext(raw, Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES);
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+    ammonia::clean(&unsafe_html)
+}
@@ -0,0 +1,77 @@
+use std::cmp::Reverse;
+use std::fmt::Write as _;
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
+
+use crate::http::is_admin;
+use crate::state::AppState;
+
+const DEFAULT_ROOM_LABEL_CAP: usize = 50;
+
+/// Prometheus text-exposition-format counters for `/api/admin/metrics`,
+/// gated the same way as `instance_stats`.
+///
+/// Per-room labels (`poker_room_users`, `poker_room_messages_total`) are
+/// opt-in via `Config::metrics_per_room` and capped at
+/// `Config::metrics_room_label_cap` rooms — busiest first, by message
+/// count — so an instance running thousands of short-lived rooms can't
+/// blow up a scraper's label cardinality. Rooms past the cap still count
+/// toward the instance-wide totals, just without their own label.
+pub async fn export(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if !is_admin(&headers) {
+        return crate::error::ApiError::forbidden("missing or invalid admin token").into_response();
+    }
+
+    let rooms = state.rooms.read().await;
+    let mut out = String::new();
+
+    let _ = writeln!(out, "# HELP poker_rooms_total Number of active rooms.");
+    let _ = writeln!(out, "# TYPE poker_rooms_total gauge");
+    let _ = writeln!(out, "poker_rooms_total {}", rooms.len());
+
+    let _ = writeln!(out, "# HELP poker_connected_users_total Number of connected users across all rooms.");
+    let _ = writeln!(out, "# TYPE poker_connected_users_total gauge");
+    let _ = writeln!(out, "poker_connected_users_total {}", rooms.values().map(|r| r.users.len()).sum::<usize>());
+
+    let _ = writeln!(out, "# HELP poker_messages_total Client messages handled across all rooms.");
+    let _ = writeln!(out, "# TYPE poker_messages_total counter");
+    let _ = writeln!(out, "poker_messages_total {}", rooms.values().map(|r| r.message_count).sum::<u64>());
+
+    let _ = writeln!(out, "# HELP poker_legacy_protocol_messages_total Client frames received in the legacy \"<type> <json>\" framing instead of the typed envelope.");
+    let _ = writeln!(out, "# TYPE poker_legacy_protocol_messages_total counter");
+    let _ = writeln!(
+        out,
+        "poker_legacy_protocol_messages_total {}",
+        state.legacy_protocol_messages.load(std::sync::atomic::Ordering::Relaxed)
+    );
+
+    if state.config.metrics_per_room {
+        let cap = state.config.metrics_room_label_cap.unwrap_or(DEFAULT_ROOM_LABEL_CAP);
+        let mut by_activity: Vec<_> = rooms.values().collect();
+        by_activity.sort_by_key(|r| Reverse(r.message_count));
+        let labeled = by_activity.iter().take(cap);
+
+        let _ = writeln!(out, "# HELP poker_room_users Connected users in a single room.");
+        let _ = writeln!(out, "# TYPE poker_room_users gauge");
+        for room in labeled.clone() {
+            let _ = writeln!(out, "poker_room_users{{room_id=\"{}\"}} {}", room.id, room.users.len());
+        }
+
+        let _ = writeln!(out, "# HELP poker_room_messages_total Client messages handled by a single room.");
+        let _ = writeln!(out, "# TYPE poker_room_messages_total counter");
+        for room in labeled {
+            let _ = writeln!(out, "poker_room_messages_total{{room_id=\"{}\"}} {}", room.id, room.message_count);
+        }
+
+        let omitted = by_activity.len().saturating_sub(cap);
+        if omitted > 0 {
+            let _ = writeln!(out, "# HELP poker_rooms_omitted_from_labels Rooms excluded from per-room labels by the cardinality cap.");
+            let _ = writeln!(out, "# TYPE poker_rooms_omitted_from_labels gauge");
+            let _ = writeln!(out, "poker_rooms_omitted_from_labels {omitted}");
+        }
+    }
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], out).into_response()
+}
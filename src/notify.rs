@@ -0,0 +1,196 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::config::WebhookConfig;
+
+/// A notable room event an external `Notifier` might want to forward
+/// (Slack, a generic webhook, email, ...). New variants get added as
+/// those destinations are wired up. `Serialize` so `WebhookNotifier` can
+/// ship it verbatim as the POST body.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationEvent {
+    RoomCreated { room_id: Uuid, name: String },
+    StoryFinished {
+        room_id: Uuid,
+        story_title: String,
+        summary: String,
+        /// Every cast vote, voter-attributed, for `SlackNotifier`'s
+        /// per-story breakdown.
+        votes: Vec<VoteRecord>,
+        /// Resolved `RoomConfig::slack_webhook_url` override for this
+        /// room, if any — takes precedence over `SlackNotifier`'s own
+        /// configured default.
+        slack_webhook_override: Option<String>,
+    },
+    SessionEnded { room_id: Uuid },
+    /// Periodic reminder for one voter in one room, listing every
+    /// unrevealed story they still haven't voted on. See `reminders.rs`.
+    VoteReminderDigest { room_id: Uuid, user_id: Uuid, user_name: String, pending_story_titles: Vec<String> },
+}
+
+/// One voter's cast value on a finished story, for `NotificationEvent::StoryFinished`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VoteRecord {
+    pub voter: String,
+    pub value: String,
+}
+
+/// A destination notifications can be sent to. Implementations do the
+/// actual I/O; `NotificationQueue` below owns retries so a flaky
+/// destination can't drop events or block whatever triggered them.
+#[async_trait::async_trait]
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &str;
+    async fn send(&self, event: &NotificationEvent) -> Result<(), String>;
+}
+
+const MAX_ATTEMPTS: u32 = 5;
+const BASE_BACKOFF: Duration = Duration::from_secs(2);
+
+struct QueuedNotification {
+    notifier_index: usize,
+    event: NotificationEvent,
+    attempt: u32,
+}
+
+/// Fans each event out to every registered `Notifier`, retrying a
+/// destination's delivery independently of the others with exponential
+/// backoff, and logging to the dead-letter log (`tracing::error!`) once
+/// a destination exhausts its attempts rather than dropping the event
+/// silently.
+pub struct NotificationQueue {
+    sender: mpsc::UnboundedSender<QueuedNotification>,
+    notifier_count: usize,
+}
+
+impl NotificationQueue {
+    pub fn start(notifiers: Vec<Arc<dyn Notifier>>) -> Self {
+        let notifier_count = notifiers.len();
+        let (sender, mut receiver) = mpsc::unbounded_channel::<QueuedNotification>();
+        let resend = sender.clone();
+        tokio::spawn(async move {
+            while let Some(queued) = receiver.recv().await {
+                let Some(notifier) = notifiers.get(queued.notifier_index) else { continue };
+                if let Err(err) = notifier.send(&queued.event).await {
+                    if queued.attempt + 1 >= MAX_ATTEMPTS {
+                        error!(
+                            notifier = notifier.name(),
+                            %err,
+                            event = ?queued.event,
+                            "dead-lettering notification after exhausting retries"
+                        );
+                        continue;
+                    }
+                    let next_attempt = queued.attempt + 1;
+                    warn!(notifier = notifier.name(), %err, next_attempt, "notification delivery failed, retrying");
+                    let resend = resend.clone();
+                    let next = QueuedNotification {
+                        notifier_index: queued.notifier_index,
+                        event: queued.event,
+                        attempt: next_attempt,
+                    };
+                    tokio::spawn(async move {
+                        tokio::time::sleep(BASE_BACKOFF * 2u32.pow(next.attempt.min(4))).await;
+                        let _ = resend.send(next);
+                    });
+                }
+            }
+        });
+        Self { sender, notifier_count }
+    }
+
+    /// Enqueues `event` for delivery to every registered notifier.
+    pub fn notify(&self, event: NotificationEvent) {
+        for notifier_index in 0..self.notifier_count {
+            let _ = self.sender.send(QueuedNotification { notifier_index, event: event.clone(), attempt: 0 });
+        }
+    }
+}
+
+/// Delivers each `NotificationEvent` as a signed JSON POST to a
+/// configured URL (see `Config::webhooks`). Retries/backoff are handled
+/// by `NotificationQueue` — `send` just reports success or failure.
+pub struct WebhookNotifier {
+    url: String,
+    secret: Option<String>,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookConfig) -> Self {
+        Self { url: config.url, secret: config.secret, client: reqwest::Client::new() }
+    }
+}
+
+#[async_trait::async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        &self.url
+    }
+
+    async fn send(&self, event: &NotificationEvent) -> Result<(), String> {
+        let body = serde_json::to_vec(event).map_err(|err| err.to_string())?;
+        let mut request = self.client.post(&self.url).header("Content-Type", "application/json");
+        if let Some(secret) = &self.secret {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts keys of any length");
+            mac.update(&body);
+            let signature = hex::encode(mac.finalize().into_bytes());
+            request = request.header("X-Webhook-Signature", format!("sha256={signature}"));
+        }
+        let response = request.body(body).send().await.map_err(|err| err.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("webhook returned {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+/// Posts a summary of a finished story — votes and consensus value — to
+/// a Slack incoming webhook. Ignores every other `NotificationEvent`
+/// variant. The URL can come from `Config::slack_webhook_url` or be
+/// overridden per room (see `RoomConfig::slack_webhook_url`); the latter
+/// wins when both are set.
+pub struct SlackNotifier {
+    default_url: Option<String>,
+    client: reqwest::Client,
+}
+
+impl SlackNotifier {
+    pub fn new(default_url: Option<String>) -> Self {
+        Self { default_url, client: reqwest::Client::new() }
+    }
+}
+
+#[async_trait::async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    async fn send(&self, event: &NotificationEvent) -> Result<(), String> {
+        let NotificationEvent::StoryFinished { story_title, summary, votes, slack_webhook_override, .. } = event else {
+            return Ok(());
+        };
+        let Some(url) = slack_webhook_override.clone().or_else(|| self.default_url.clone()) else {
+            return Ok(());
+        };
+        let mut text = format!("*{story_title}* finished — consensus: *{summary}*");
+        if !votes.is_empty() {
+            let breakdown: Vec<String> = votes.iter().map(|vote| format!("{}: {}", vote.voter, vote.value)).collect();
+            text.push_str(&format!("\nVotes: {}", breakdown.join(", ")));
+        }
+        let response =
+            self.client.post(&url).json(&serde_json::json!({ "text": text })).send().await.map_err(|err| err.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("slack returned {}", response.status()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+const MAX_BYTES: u64 = 64 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Fetches `url` and extracts its `<title>` text, for
+/// `ClientMessage::AddStory` on a bare link with no description (see
+/// `Config::fetch_page_titles`). Bounded by `TIMEOUT` and `MAX_BYTES` so
+/// a slow or huge page can't stall the story or blow up memory.
+pub async fn fetch_title(url: &str) -> Result<String, String> {
+    let client = reqwest::Client::builder().timeout(TIMEOUT).build().map_err(|err| err.to_string())?;
+    let mut response = client.get(url).send().await.map_err(|err| err.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("page returned {}", response.status()));
+    }
+    if response.content_length().is_some_and(|len| len > MAX_BYTES) {
+        return Err("page exceeds size limit".to_string());
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|err| err.to_string())? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() as u64 > MAX_BYTES {
+            return Err("page exceeds size limit".to_string());
+        }
+    }
+    let body = String::from_utf8_lossy(&bytes);
+    extract_title(&body).ok_or_else(|| "no <title> found".to_string())
+}
+
+fn extract_title(html: &str) -> Option<String> {
+    let lower = html.to_lowercase();
+    let open = lower.find("<title")?;
+    let after_open = html[open..].find('>')? + open + 1;
+    let close_rel = html[after_open..].to_lowercase().find("</title")?;
+    let title = html[after_open..after_open + close_rel].trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::room::{LateVotePolicy, Room, RoomConfig, Story, StoryPhase, Vote};
+use crate::state::AppState;
+
+/// On-disk shape of a story, wider than `snapshot::StorySnapshot`: it
+/// keeps votes and reveal state so a restart doesn't lose in-progress
+/// work the way a deliberate export/template/clone is meant to.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct PersistedStory {
+    id: Uuid,
+    title: String,
+    description: String,
+    final_estimate: Option<String>,
+    attachments: Vec<String>,
+    votes: HashMap<Uuid, Vote>,
+    revealed: bool,
+    deadline: Option<DateTime<Utc>>,
+    late_vote_policy: LateVotePolicy,
+    #[serde(default)]
+    checklist: HashMap<String, bool>,
+    #[serde(default)]
+    story_url: Option<String>,
+    #[serde(default)]
+    issue_key: Option<String>,
+    #[serde(default)]
+    voter_scope: Option<Vec<Uuid>>,
+    #[serde(default)]
+    phase: StoryPhase,
+    #[serde(default)]
+    phase_deadline: Option<DateTime<Utc>>,
+}
+
+impl From<&Story> for PersistedStory {
+    fn from(story: &Story) -> Self {
+        Self {
+            id: story.id,
+            title: story.title.clone(),
+            description: story.description.clone(),
+            final_estimate: story.final_estimate.clone(),
+            attachments: story.attachments.clone(),
+            votes: story.votes.clone(),
+            revealed: story.revealed,
+            deadline: story.deadline,
+            late_vote_policy: story.late_vote_policy,
+            checklist: story.checklist.clone(),
+            story_url: story.story_url.clone(),
+            issue_key: story.issue_key.clone(),
+            voter_scope: story.voter_scope.clone(),
+            phase: story.phase,
+            phase_deadline: story.phase_deadline,
+        }
+    }
+}
+
+impl From<PersistedStory> for Story {
+    fn from(p: PersistedStory) -> Self {
+        let mut story = Story::new(p.title, p.description);
+        story.id = p.id;
+        story.final_estimate = p.final_estimate;
+        story.attachments = p.attachments;
+        story.votes = p.votes;
+        story.revealed = p.revealed;
+        story.deadline = p.deadline;
+        story.late_vote_policy = p.late_vote_policy;
+        story.checklist = p.checklist;
+        story.story_url = p.story_url;
+        story.issue_key = p.issue_key;
+        story.voter_scope = p.voter_scope;
+        story.phase = p.phase;
+        story.phase_deadline = p.phase_deadline;
+        story
+    }
+}
+
+/// On-disk shape of a room. Connected users aren't included — there's no
+/// socket on the other end to reconnect after a restart, so the restored
+/// room simply starts empty and waits for everyone to rejoin.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct PersistedRoom {
+    pub(crate) id: Uuid,
+    name: String,
+    join_code: String,
+    owner_id: Uuid,
+    config: RoomConfig,
+    stories: Vec<PersistedStory>,
+    current_story: Option<usize>,
+    created_at: DateTime<Utc>,
+    persistent: bool,
+    scheduled_for: Option<DateTime<Utc>>,
+    on_break: bool,
+    #[serde(default)]
+    password: Option<String>,
+}
+
+impl From<&Room> for PersistedRoom {
+    fn from(room: &Room) -> Self {
+        Self {
+            id: room.id,
+            name: room.name.clone(),
+            join_code: room.join_code.clone(),
+            owner_id: room.owner_id,
+            config: room.config.clone(),
+            stories: room.stories.iter().map(PersistedStory::from).collect(),
+            current_story: room.current_story,
+            created_at: room.created_at,
+            persistent: room.persistent,
+            scheduled_for: room.scheduled_for,
+            on_break: room.on_break,
+            password: room.password.clone(),
+        }
+    }
+}
+
+impl PersistedRoom {
+    pub(crate) fn into_room(self) -> Room {
+        let mut room = Room::new(self.name, self.owner_id);
+        room.id = self.id;
+        room.join_code = self.join_code;
+        room.config = self.config;
+        room.stories = self.stories.into_iter().map(Story::from).collect();
+        room.current_story = self.current_story;
+        room.created_at = self.created_at;
+        room.persistent = self.persistent;
+        room.scheduled_for = self.scheduled_for;
+        room.on_break = self.on_break;
+        room.password = self.password;
+        room
+    }
+}
+
+/// SQLite-backed room persistence, enabled by setting `sqlite_path` in
+/// the config file. The whole room table is replaced on each save rather
+/// than diffed, which is simple and plenty fast at the handful-of-rooms
+/// scale a single instance is expected to hold.
+pub struct Store {
+    conn: Mutex<Connection>,
+}
+
+/// One row of previously recorded estimation history (story, estimate,
+/// actual outcome, date), imported in bulk from an external CSV export.
+#[derive(Debug, Clone)]
+pub struct HistoryRow {
+    pub story: String,
+    pub estimate: String,
+    pub actual: String,
+    pub date: String,
+}
+
+impl Store {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS rooms (id TEXT PRIMARY KEY, data TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS estimation_history (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 story TEXT NOT NULL,
+                 estimate TEXT NOT NULL,
+                 actual TEXT NOT NULL,
+                 date TEXT NOT NULL
+             )",
+        )?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    /// Appends imported history rows for velocity/calibration analytics.
+    /// Used by the `/api/admin/analytics/import` endpoint.
+    pub fn insert_history(&self, rows: &[HistoryRow]) {
+        let conn = self.conn.lock().unwrap();
+        for row in rows {
+            let _ = conn.execute(
+                "INSERT INTO estimation_history (story, estimate, actual, date) VALUES (?1, ?2, ?3, ?4)",
+                params![row.story, row.estimate, row.actual, row.date],
+            );
+        }
+    }
+
+    fn save_all(&self, rooms: &HashMap<Uuid, Room>) {
+        let conn = self.conn.lock().unwrap();
+        let _ = conn.execute("DELETE FROM rooms", []);
+        for room in rooms.values() {
+            let data = serde_json::to_string(&PersistedRoom::from(room)).expect("PersistedRoom always serializes");
+            let _ = conn.execute("INSERT INTO rooms (id, data) VALUES (?1, ?2)", params![room.id.to_string(), data]);
+        }
+    }
+
+    fn load_all(&self) -> Vec<Room> {
+        let conn = self.conn.lock().unwrap();
+        let Ok(mut stmt) = conn.prepare("SELECT data FROM rooms") else { return Vec::new() };
+        let Ok(rows) = stmt.query_map([], |row| row.get::<_, String>(0)) else { return Vec::new() };
+        rows.filter_map(Result::ok)
+            .filter_map(|data| serde_json::from_str::<PersistedRoom>(&data).ok())
+            .map(PersistedRoom::into_room)
+            .collect()
+    }
+}
+
+const PERSIST_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Background task: periodically snapshots every live room to SQLite.
+pub async fn run(state: AppState, store: Arc<Store>) {
+    let mut interval = tokio::time::interval(PERSIST_INTERVAL);
+    loop {
+        interval.tick().await;
+        let rooms = state.rooms.read().await;
+        store.save_all(&rooms);
+    }
+}
+
+/// Loads whatever was persisted from a previous run into `state`. Called
+/// once at startup, before the server starts accepting connections.
+pub async fn restore(state: &AppState, store: &Store) {
+    let rooms = store.load_all();
+    if rooms.is_empty() {
+        return;
+    }
+    let mut guard = state.rooms.write().await;
+    for room in rooms {
+        guard.insert(room.id, room);
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::notify::NotificationEvent;
+use crate::room::Role;
+use crate::state::AppState;
+
+/// Background task: on `Config::vote_reminder_interval_secs`, scans every
+/// room for voters with unvoted, unrevealed stories and queues one digest
+/// notification per voter (not one per story) listing what's still
+/// outstanding. Does nothing if the interval isn't configured.
+pub async fn run(state: AppState) {
+    let Some(interval_secs) = state.config.vote_reminder_interval_secs else { return };
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+    loop {
+        interval.tick().await;
+        tick(&state).await;
+    }
+}
+
+async fn tick(state: &AppState) {
+    let rooms = state.rooms.read().await;
+    for room in rooms.values() {
+        let mut pending: HashMap<Uuid, Vec<String>> = HashMap::new();
+        for story in &room.stories {
+            if story.revealed {
+                continue;
+            }
+            for user in room.users.values() {
+                if user.role != Role::Voter || user.is_bot {
+                    continue;
+                }
+                if !story.votes.contains_key(&user.id) {
+                    pending.entry(user.id).or_default().push(story.title.clone());
+                }
+            }
+        }
+        for (user_id, pending_story_titles) in pending {
+            let Some(user) = room.users.get(&user_id) else { continue };
+            state.notifications.notify(NotificationEvent::VoteReminderDigest {
+                room_id: room.id,
+                user_id,
+                user_name: user.name.clone(),
+                pending_story_titles,
+            });
+        }
+    }
+}
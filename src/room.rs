@@ -0,0 +1,650 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::ws::ServerMessage;
+
+/// How long a room may sit with no WebSocket activity before the
+/// background GC task (see `cleanup::run`) removes it.
+pub const ROOM_IDLE_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Capacity of each room's outbound broadcast channel. Slow/disconnected
+/// receivers that fall this far behind get a `Lagged` error and resync
+/// by requesting full state instead of replaying every message.
+pub const BROADCAST_CAPACITY: usize = 256;
+
+/// How long a participant may go without sending any message before
+/// they're dropped from the room as inactive.
+pub const PARTICIPANT_IDLE_TTL: Duration = Duration::from_secs(45 * 60);
+
+/// How long before `PARTICIPANT_IDLE_TTL` a reminder is broadcast, giving
+/// a quiet-but-present user a chance to act before being removed.
+pub const PARTICIPANT_IDLE_REMINDER_LEAD: Duration = Duration::from_secs(5 * 60);
+
+/// Characters used for join codes. Ambiguous-looking ones (0/O, 1/I/L)
+/// are left out so codes read back correctly over voice or chat.
+const JOIN_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const JOIN_CODE_LEN: usize = 6;
+
+fn generate_join_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..JOIN_CODE_LEN)
+        .map(|_| *JOIN_CODE_ALPHABET.choose(&mut rng).unwrap() as char)
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Owner,
+    Voter,
+    Watcher,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    pub id: Uuid,
+    pub name: String,
+    pub role: Role,
+    #[serde(skip, default = "Instant::now")]
+    pub last_seen: Instant,
+    /// When this user joined, used to tell late joiners apart from
+    /// participants who were present when the current story started
+    /// voting (see `Room::all_eligible_voted`).
+    #[serde(skip, default = "Instant::now")]
+    pub joined_at: Instant,
+    /// Set when this user joined via the room's watcher-only guest link
+    /// (see `Room::watcher_guest_token`). Locks them to `Watcher` even
+    /// against an owner's `ChangeRole`, so a link shared with outside
+    /// stakeholders can't accidentally (or deliberately) be escalated.
+    pub guest_locked: bool,
+    /// Set for connections that identify themselves as automation (an
+    /// API-token-driven importer, a dashboard, etc.) rather than a human
+    /// estimating stories. Bots are rendered distinctly in the user list
+    /// and never count toward `all_eligible_voted`, so an importer bot
+    /// can't silently block a reveal by never casting a vote.
+    #[serde(default)]
+    pub is_bot: bool,
+}
+
+/// A single cast vote and when it was cast. The timestamp is only
+/// surfaced to clients once the story is revealed (see `Story::view`),
+/// so it can't be used to infer who voted quickly before seeing others.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vote {
+    pub value: String,
+    pub voted_at: DateTime<Utc>,
+    /// True if this vote was cast after the story's `deadline` under
+    /// `LateVotePolicy::Flag`.
+    pub late: bool,
+}
+
+/// What a per-room API token (see `Room::api_tokens`) is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiTokenScope {
+    /// Create, edit, and delete stories via the `/api/rooms/{room}/stories`
+    /// REST endpoints — the full backlog-management surface, not just
+    /// adding.
+    AddStories,
+    ReadResults,
+    /// Read the room's full state and delete the room entirely — see
+    /// `http::room_state_via_token`/`http::delete_room_via_token`. The
+    /// REST equivalent of being the room owner, for scripts that set up
+    /// and tear down sessions without ever opening a WebSocket.
+    ManageRoom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LateVotePolicy {
+    /// Late votes are rejected outright.
+    Reject,
+    /// Late votes are accepted but flagged (`Vote::late`) so the reveal
+    /// UI can call them out.
+    Flag,
+}
+
+/// Where a story is in the facilitation flow, tracked explicitly on the
+/// server (rather than left for each client to infer from `revealed`,
+/// votes cast, etc.) so every connected client's UI agrees on what step
+/// the team is on. The owner sets it directly via
+/// `ClientMessage::SetStoryPhase`; `Reveal`, `Reset`, `Revote` and `Skip`
+/// also move it, and the server moves a story to `Revealed` on its own
+/// when auto-reveal fires or a story timer runs out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoryPhase {
+    /// The story is being introduced; nobody's voting yet.
+    Presenting,
+    /// Open discussion before anyone commits to an estimate.
+    Discussing,
+    /// Votes are being cast.
+    Voting,
+    /// Votes are revealed. Kept in sync with `Story::revealed`.
+    Revealed,
+    /// The story was skipped as out of scope via `ClientMessage::Skip`
+    /// instead of voted on. Excluded from `Room::all_eligible_voted` and
+    /// marked as such in exports, rather than carrying a fake estimate.
+    Skipped,
+}
+
+impl Default for StoryPhase {
+    fn default() -> Self {
+        Self::Presenting
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Story {
+    pub id: Uuid,
+    pub title: String,
+    /// Raw Markdown as entered by the user.
+    pub description: String,
+    /// Sanitized HTML rendering of `description`, recomputed on every
+    /// edit so clients never need their own Markdown renderer.
+    pub description_html: String,
+    pub votes: HashMap<Uuid, Vote>,
+    pub revealed: bool,
+    pub final_estimate: Option<String>,
+    /// Optional cutoff after which `late_vote_policy` applies to new
+    /// votes for this story.
+    pub deadline: Option<DateTime<Utc>>,
+    pub late_vote_policy: LateVotePolicy,
+    /// Image/reference links attached to the story (mockups, screenshots,
+    /// design docs). Just URLs — rendering them is a client concern.
+    pub attachments: Vec<String>,
+    /// Per-item completion state for the room's definition-of-ready
+    /// checklist (`RoomConfig::checklist_items`), keyed by item text.
+    /// Items added to the room after this story already existed are
+    /// simply absent here until the owner ticks them.
+    pub checklist: HashMap<String, bool>,
+    /// Link to this story in an external tracker (Jira, GitHub, GitLab
+    /// issue/PR, ...). Set via `set_story_url`, which also derives
+    /// `issue_key`.
+    pub story_url: Option<String>,
+    /// Short key parsed out of `story_url` (e.g. `"PROJ-123"` or
+    /// `"owner/repo#45"`) for known trackers, used for dedup, exports,
+    /// and estimate write-back instead of re-parsing the URL everywhere
+    /// that needs it. `None` when `story_url` is unset or unrecognized.
+    pub issue_key: Option<String>,
+    /// When set, only these users' votes count toward `all_eligible_voted`
+    /// and `stats::compute` for this story — everyone else is simply not
+    /// asked to vote on it (the client renders them as not-applicable).
+    /// `None` (the default) means every voter in the room is in scope, as
+    /// before this field existed. Set via `ClientMessage::SetStoryVoterScope`
+    /// for cross-functional rooms estimating a mixed backlog, e.g. scoping
+    /// a backend-only story to the backend voters.
+    pub voter_scope: Option<Vec<Uuid>>,
+    /// Current step in the facilitation flow. See `StoryPhase`.
+    pub phase: StoryPhase,
+    /// Optional countdown for the current phase, set alongside it by
+    /// `ClientMessage::SetStoryPhase`'s `timer_secs`. Purely advisory —
+    /// the server doesn't auto-transition when it elapses, it's up to
+    /// the owner (or their UI) to move on.
+    pub phase_deadline: Option<DateTime<Utc>>,
+}
+
+/// What's actually sent to clients for a story: votes are hidden behind
+/// `voted_user_ids` until `revealed`, at which point the full `votes`
+/// map (value + timestamp) is included instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryView {
+    pub id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub description_html: String,
+    pub voted_user_ids: Vec<Uuid>,
+    pub votes: Option<HashMap<Uuid, Vote>>,
+    /// Cast values with no per-voter attribution, populated instead of
+    /// `votes` when `RoomConfig::anonymous_reveal` is on.
+    pub anonymous_votes: Option<Vec<String>>,
+    pub revealed: bool,
+    pub final_estimate: Option<String>,
+    pub deadline: Option<DateTime<Utc>>,
+    pub late_vote_policy: LateVotePolicy,
+    pub attachments: Vec<String>,
+    pub checklist: HashMap<String, bool>,
+    pub story_url: Option<String>,
+    pub issue_key: Option<String>,
+    pub voter_scope: Option<Vec<Uuid>>,
+    /// Current step in the facilitation flow. See `StoryPhase`.
+    pub phase: StoryPhase,
+    /// Optional countdown for the current phase, set alongside it by
+    /// `ClientMessage::SetStoryPhase`'s `timer_secs`. Purely advisory —
+    /// the server doesn't auto-transition when it elapses, it's up to
+    /// the owner (or their UI) to move on.
+    pub phase_deadline: Option<DateTime<Utc>>,
+    /// Mean/median/mode/min/max/standard-deviation of the numeric votes,
+    /// computed server-side (see `stats::compute`) so every client shows
+    /// the same numbers without each re-deriving them. `None` until the
+    /// story is revealed.
+    pub stats: Option<crate::stats::VoteStats>,
+}
+
+impl Story {
+    pub fn new(title: String, description: String) -> Self {
+        let description_html = crate::markdown::render(&description);
+        Self {
+            id: Uuid::new_v4(),
+            title,
+            description,
+            description_html,
+            votes: HashMap::new(),
+            revealed: false,
+            final_estimate: None,
+            deadline: None,
+            late_vote_policy: LateVotePolicy::Flag,
+            attachments: Vec::new(),
+            checklist: HashMap::new(),
+            story_url: None,
+            issue_key: None,
+            voter_scope: None,
+            phase: StoryPhase::Presenting,
+            phase_deadline: None,
+        }
+    }
+
+    /// Sets (or clears) `story_url` and re-derives `issue_key` from it.
+    pub fn set_story_url(&mut self, story_url: Option<String>) {
+        self.issue_key = story_url.as_deref().and_then(crate::issue_key::extract);
+        self.story_url = story_url;
+    }
+
+    /// Whether `user_id` is allowed to vote on this story: always true
+    /// unless `voter_scope` narrows it down to a specific subset.
+    pub fn is_eligible_voter(&self, user_id: Uuid) -> bool {
+        self.voter_scope.as_ref().is_none_or(|scope| scope.contains(&user_id))
+    }
+
+    /// Remaps every cast vote to the closest numeric value present in
+    /// `new_deck`. Votes whose value doesn't parse as a number (e.g. a
+    /// previous deck's "?") are left untouched since there's no sensible
+    /// distance to migrate them by.
+    pub fn migrate_votes(&mut self, new_deck: &[String]) {
+        let numeric_options: Vec<(String, f64)> =
+            new_deck.iter().filter_map(|card| card.parse::<f64>().ok().map(|v| (card.clone(), v))).collect();
+        if numeric_options.is_empty() {
+            return;
+        }
+        for vote in self.votes.values_mut() {
+            let Ok(current) = vote.value.parse::<f64>() else { continue };
+            if let Some((closest, _)) = numeric_options
+                .iter()
+                .min_by(|(_, a), (_, b)| (a - current).abs().total_cmp(&(b - current).abs()))
+            {
+                vote.value = closest.clone();
+            }
+        }
+    }
+
+    /// Whether a vote cast right now would be late, and if so whether it
+    /// should be rejected outright.
+    pub fn vote_lateness(&self) -> (bool, bool) {
+        let is_late = self.deadline.is_some_and(|d| Utc::now() > d);
+        let rejected = is_late && self.late_vote_policy == LateVotePolicy::Reject;
+        (is_late, rejected)
+    }
+
+    pub fn set_description(&mut self, description: String) {
+        self.description_html = crate::markdown::render(&description);
+        self.description = description;
+    }
+
+    /// Rough byte-size estimate for the memory-budget guardrail (see
+    /// `Room::approx_memory_bytes`). Not an exact allocator accounting —
+    /// just enough to compare against a configured ceiling.
+    pub fn approx_memory_bytes(&self) -> usize {
+        let votes_bytes: usize = self.votes.values().map(|v| v.value.len() + 48).sum();
+        self.title.len()
+            + self.description.len()
+            + self.description_html.len()
+            + self.final_estimate.as_deref().map_or(0, str::len)
+            + self.attachments.iter().map(String::len).sum::<usize>()
+            + votes_bytes
+            + self.checklist.keys().map(|k| k.len() + 1).sum::<usize>()
+            + self.story_url.as_deref().map_or(0, str::len)
+            + self.issue_key.as_deref().map_or(0, str::len)
+            + self.voter_scope.as_ref().map_or(0, |scope| scope.len() * 16)
+            + 64 // id, flags, timestamps
+    }
+
+    pub fn view(&self, config: &RoomConfig, users: &HashMap<Uuid, User>) -> StoryView {
+        StoryView {
+            id: self.id,
+            title: self.title.clone(),
+            description: self.description.clone(),
+            description_html: self.description_html.clone(),
+            voted_user_ids: self.votes.keys().copied().collect(),
+            votes: (self.revealed && !config.anonymous_reveal).then(|| self.votes.clone()),
+            anonymous_votes: (self.revealed && config.anonymous_reveal)
+                .then(|| self.votes.values().map(|v| v.value.clone()).collect()),
+            revealed: self.revealed,
+            final_estimate: self.final_estimate.clone(),
+            deadline: self.deadline,
+            late_vote_policy: self.late_vote_policy,
+            attachments: self.attachments.clone(),
+            checklist: self.checklist.clone(),
+            story_url: self.story_url.clone(),
+            issue_key: self.issue_key.clone(),
+            voter_scope: self.voter_scope.clone(),
+            phase: self.phase,
+            phase_deadline: self.phase_deadline,
+            stats: self.revealed.then(|| crate::stats::compute(self, config, users)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomConfig {
+    pub deck: Vec<String>,
+    /// When true, users with the `Watcher` role are left out of the
+    /// participant list sent to everyone, so voters aren't distracted by
+    /// who's merely observing.
+    #[serde(default)]
+    pub hide_watchers: bool,
+    /// Delay between "everyone eligible has voted" and the automatic
+    /// reveal, giving a last straggler a moment to change their vote
+    /// before it's locked in. `None` reveals immediately (the default).
+    #[serde(default)]
+    pub auto_reveal_delay_secs: Option<u64>,
+    /// Per-role vote weight used by `stats::compute`'s weighted average.
+    /// Roles not present here default to a weight of 1.0.
+    #[serde(default)]
+    pub role_weights: HashMap<Role, f64>,
+    /// BCP-47 locale hint (e.g. `"de-DE"`) the owner can set so
+    /// server-generated exports format computed numbers the way that
+    /// team expects. `None` (the default) uses the server's plain
+    /// `.`-decimal formatting — see `stats::format_number_for_locale`.
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Definition-of-ready checklist the owner can tick per story before
+    /// opening voting (e.g. "acceptance criteria present", "dependencies
+    /// known"). Purely informational at the server level — nothing stops
+    /// voting on a story with unticked items.
+    #[serde(default)]
+    pub checklist_items: Vec<String>,
+    /// When true, the room owner sees live (not-yet-revealed) vote values
+    /// as they're cast, via `ServerMessage::OwnerLiveVotes` — useful for
+    /// interview calibration sessions where the interviewer needs to see
+    /// answers as they come in. Everyone else still sees only
+    /// `voted_user_ids` until the story is revealed.
+    #[serde(default)]
+    pub owner_sees_live_votes: bool,
+    /// When true, the room owner is left out of the participant list sent
+    /// to everyone (including themselves) the same way `hide_watchers`
+    /// hides watchers — for an external facilitator who runs the session
+    /// but shouldn't show up as a team member in the user list or any
+    /// export derived from it. The owner keeps full control of the room;
+    /// only their presence in `RoomState`/`UsersDelta` is affected.
+    #[serde(default)]
+    pub hide_owner: bool,
+    /// Whether a vote can still be cast/changed after a story is
+    /// revealed. True (the current behavior, preserved as the default)
+    /// lets a straggler's late correction still count; false locks votes
+    /// in at reveal time for a harder-edged process.
+    #[serde(default = "RoomConfig::default_allow_vote_change_after_reveal")]
+    pub allow_vote_change_after_reveal: bool,
+    /// When true, a reveal shows the set of cast values (see
+    /// `StoryView::anonymous_votes`) without attributing any of them to a
+    /// particular voter, for teams that want to discuss the spread
+    /// without anchoring on who picked what. `voted_user_ids` (who *has*
+    /// voted, not what they picked) is unaffected.
+    #[serde(default)]
+    pub anonymous_reveal: bool,
+    /// When true, setting a story's final estimate also writes it back to
+    /// the mapped JIRA issue's story-points field (see `Config::jira`).
+    /// Off by default — per-room opt-in, since not every room's stories
+    /// are backed by a JIRA issue.
+    #[serde(default)]
+    pub jira_writeback: bool,
+    /// Per-room override for `Config::slack_webhook_url`, for a room
+    /// whose finished-story notifications should post somewhere other
+    /// than the instance-wide default Slack channel. `None` (the
+    /// default) falls back to the configured default, if any.
+    #[serde(default)]
+    pub slack_webhook_url: Option<String>,
+}
+
+impl RoomConfig {
+    fn default_allow_vote_change_after_reveal() -> bool {
+        true
+    }
+}
+
+impl Default for RoomConfig {
+    fn default() -> Self {
+        Self {
+            deck: vec!["0", "1", "2", "3", "5", "8", "13", "21", "?"].into_iter().map(String::from).collect(),
+            hide_watchers: false,
+            auto_reveal_delay_secs: None,
+            role_weights: HashMap::new(),
+            locale: None,
+            checklist_items: Vec::new(),
+            owner_sees_live_votes: false,
+            hide_owner: false,
+            allow_vote_change_after_reveal: true,
+            anonymous_reveal: false,
+            jira_writeback: false,
+            slack_webhook_url: None,
+        }
+    }
+}
+
+pub struct Room {
+    pub id: Uuid,
+    pub name: String,
+    /// Short, human-typeable code for joining without pasting the full
+    /// room URL (e.g. reading it aloud on a call).
+    pub join_code: String,
+    pub owner_id: Uuid,
+    pub config: RoomConfig,
+    pub users: HashMap<Uuid, User>,
+    pub stories: Vec<Story>,
+    pub current_story: Option<usize>,
+    /// When the current story was selected; users who join after this
+    /// are "late joiners" and don't block auto-reveal by not voting.
+    pub story_started_at: Option<Instant>,
+    pub created_at: DateTime<Utc>,
+    pub last_activity: Instant,
+    /// When set, the idle GC never removes this room regardless of
+    /// `idle_for()`. Intended for long-lived team rooms.
+    pub persistent: bool,
+    /// When set, this room was pre-created ahead of a planned session and
+    /// is expected to sit idle (but alive) until that time — the owner
+    /// can share the join link/code in advance.
+    pub scheduled_for: Option<DateTime<Utc>>,
+    /// Owner-toggled room-wide break/coffee mode. Purely informational at
+    /// the server level — it's up to clients to pause their own voting
+    /// UI while this is set.
+    pub on_break: bool,
+    /// Unguessable token for the watcher-only guest link (see
+    /// `ClientMessage::RequestGuestLink`). A `Join` carrying this token
+    /// is forced into `Role::Watcher` regardless of what it asked for.
+    pub watcher_guest_token: String,
+    /// Unguessable token for the spectator link (see
+    /// `ClientMessage::RequestSpectatorLink`), gating `handler::observe_route`
+    /// the same way `watcher_guest_token` gates a `Watcher` `Join`. Unlike a
+    /// `Watcher`, a spectator never joins as a participant at all — no
+    /// `User` entry, no name, just the same read-only broadcast stream
+    /// `observe_route` already sends anyone (now only to holders of this
+    /// token).
+    pub spectator_token: String,
+    /// Scoped API tokens minted by the owner (see
+    /// `ClientMessage::MintApiToken`), keyed by the token string, so
+    /// automations can add stories or read results without an
+    /// instance-wide admin key.
+    pub api_tokens: HashMap<String, Vec<ApiTokenScope>>,
+    /// Normalized (trimmed, lowercased) names banned from rejoining this
+    /// room for its lifetime, set via `ClientMessage::Ban` and checked on
+    /// every `ClientMessage::Join`. See `handler::normalize_name` for why
+    /// a name, rather than a stable id, is the ban key.
+    pub banned_names: std::collections::HashSet<String>,
+    /// Optional password set by the room creator (see
+    /// `http::CreateRoomRequest::password`). When set, the WS handshake
+    /// must carry the matching password as a query param or it's rejected
+    /// with `ServerMessage::JoinRejected` before the connection is ever
+    /// added as a participant. Never serialized into `RoomConfig` —
+    /// everyone in the room already knows it's password-protected, but
+    /// the password itself is never broadcast.
+    pub password: Option<String>,
+    /// Bumped on every `RoomState`/`UsersDelta` broadcast. Lets clients
+    /// notice a gap (a delta they never saw) and request a full resync
+    /// instead of silently drifting from the server's user list.
+    pub state_seq: u64,
+    /// Count of client messages this room has handled, for the
+    /// per-room message-rate metric in `metrics.rs`. Monotonic for the
+    /// room's lifetime; operators compute a rate from successive scrapes.
+    pub message_count: u64,
+    /// The room's single outbound fan-out channel. Every connection
+    /// (including multiple tabs for the same human) gets its own
+    /// `subscribe()`d receiver rather than the room holding one sender
+    /// per user — so there's no shared slot for a second tab to
+    /// overwrite, and closing one tab has no effect on any other
+    /// connection's stream. Each tab is tracked as its own `User` entry
+    /// (its own `Uuid`, assigned on `ClientMessage::Join`), so it only
+    /// stops appearing in `Room::users` once its own connection closes
+    /// and is reaped, not when a sibling tab closes.
+    pub sender: broadcast::Sender<ServerMessage>,
+}
+
+impl Room {
+    pub fn new(name: String, owner_id: Uuid) -> Self {
+        let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            join_code: generate_join_code(),
+            owner_id,
+            config: RoomConfig::default(),
+            users: HashMap::new(),
+            stories: Vec::new(),
+            current_story: None,
+            story_started_at: None,
+            created_at: Utc::now(),
+            last_activity: Instant::now(),
+            persistent: false,
+            scheduled_for: None,
+            on_break: false,
+            watcher_guest_token: Uuid::new_v4().to_string(),
+            spectator_token: Uuid::new_v4().to_string(),
+            api_tokens: HashMap::new(),
+            banned_names: std::collections::HashSet::new(),
+            password: None,
+            state_seq: 0,
+            message_count: 0,
+            sender,
+        }
+    }
+
+    /// Advances and returns the state sequence number; call once per
+    /// `RoomState`/`UsersDelta` broadcast, never on a per-client resync.
+    pub fn bump_seq(&mut self) -> u64 {
+        self.state_seq += 1;
+        self.state_seq
+    }
+
+    pub fn touch(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    /// Rough byte-size estimate across all stories, for the memory-budget
+    /// guardrail in `Config::room_memory_budget_bytes`.
+    pub fn approx_memory_bytes(&self) -> usize {
+        self.stories.iter().map(Story::approx_memory_bytes).sum()
+    }
+
+    /// Evicts the oldest finished (`revealed`) stories, in order, until
+    /// the room plus `incoming_bytes` of not-yet-added data fits under
+    /// `budget`, or there are no finished stories left to evict. Returns
+    /// whether the room is under budget afterward — `false` means the
+    /// caller should refuse whatever triggered the check.
+    pub fn evict_to_fit(&mut self, budget: usize, incoming_bytes: usize) -> bool {
+        while self.approx_memory_bytes() + incoming_bytes > budget {
+            let Some(pos) = self.stories.iter().position(|s| s.revealed) else { return false };
+            self.stories.remove(pos);
+            self.current_story = match self.current_story {
+                Some(cur) if cur == pos => None,
+                Some(cur) if cur > pos => Some(cur - 1),
+                other => other,
+            };
+        }
+        true
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        self.last_activity.elapsed()
+    }
+
+    /// Seconds until this room would be GC'd if left idle from now,
+    /// or `None` if it's exempt via `persistent`. `ttl` is the effective
+    /// idle TTL — `ROOM_IDLE_TTL` unless overridden by
+    /// `Config::room_idle_ttl_secs`.
+    pub fn expires_in(&self, ttl: Duration) -> Option<Duration> {
+        if self.persistent {
+            None
+        } else {
+            Some(ttl.saturating_sub(self.idle_for()))
+        }
+    }
+
+    pub fn broadcast(&self, message: ServerMessage) {
+        // No receivers is the common case between connections; the send
+        // error there is expected and not worth logging.
+        let _ = self.sender.send(message);
+    }
+
+    /// True once every voter present before the current story started
+    /// has cast a vote. Voters who joined after `story_started_at` are
+    /// late joiners and are excluded so their absence can't stall
+    /// auto-reveal indefinitely, as are voters outside the story's
+    /// `Story::voter_scope` when it's narrowed to a subset.
+    pub fn all_eligible_voted(&self) -> bool {
+        let Some(story) = self.current_story.and_then(|i| self.stories.get(i)) else {
+            return false;
+        };
+        if story.phase == StoryPhase::Skipped {
+            return false;
+        }
+        let started_at = self.story_started_at;
+        let eligible = self.users.values().filter(|u| {
+            u.role == Role::Voter
+                && !u.is_bot
+                && started_at.is_none_or(|s| u.joined_at <= s)
+                && story.is_eligible_voter(u.id)
+        });
+        let mut any = false;
+        for user in eligible {
+            any = true;
+            if !story.votes.contains_key(&user.id) {
+                return false;
+            }
+        }
+        any
+    }
+
+    /// If the current owner has been inactive longer than `grace`, hands
+    /// ownership to whichever non-bot, non-watcher participant has been
+    /// connected the longest, so a closed laptop doesn't permanently
+    /// strand the room. Returns the new owner's id if a handover
+    /// happened; does nothing (and returns `None`) if the owner is still
+    /// active, already gone with nobody to replace them, or there's no
+    /// other eligible participant. Called from `cleanup::run` when
+    /// `Config::owner_failover_grace_secs` is set.
+    pub fn maybe_failover_owner(&mut self, grace: Duration) -> Option<Uuid> {
+        let owner = self.users.get(&self.owner_id)?;
+        if owner.last_seen.elapsed() < grace {
+            return None;
+        }
+        let candidate =
+            self.users.values().filter(|u| u.id != self.owner_id && !u.is_bot && u.role != Role::Watcher).min_by_key(|u| u.joined_at)?;
+        let new_owner_id = candidate.id;
+        self.owner_id = new_owner_id;
+        Some(new_owner_id)
+    }
+}
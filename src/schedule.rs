@@ -0,0 +1,65 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::room::Room;
+use crate::state::AppState;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+}
+
+impl Recurrence {
+    fn period(self) -> ChronoDuration {
+        match self {
+            Recurrence::Daily => ChronoDuration::days(1),
+            Recurrence::Weekly => ChronoDuration::weeks(1),
+        }
+    }
+}
+
+/// A rule that pre-creates a fresh room on a repeating cadence, so a
+/// recurring team ceremony always has a join link ready ahead of time
+/// without anyone remembering to create the room manually.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringSchedule {
+    pub id: Uuid,
+    pub room_name: String,
+    pub recurrence: Recurrence,
+    pub next_run: DateTime<Utc>,
+}
+
+const SCHEDULE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+pub async fn run(state: AppState) {
+    let mut interval = tokio::time::interval(SCHEDULE_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        tick(&state).await;
+    }
+}
+
+async fn tick(state: &AppState) {
+    let mut schedules = state.schedules.write().await;
+    let now = Utc::now();
+    for schedule in schedules.iter_mut() {
+        if schedule.next_run > now {
+            continue;
+        }
+        let run_time = schedule.next_run;
+        schedule.next_run += schedule.recurrence.period();
+        let mut rooms = state.rooms.write().await;
+        if let Some(max_rooms) = state.config.max_rooms {
+            if rooms.len() >= max_rooms {
+                continue;
+            }
+        }
+        let owner_id = Uuid::new_v4();
+        let mut room = Room::new(schedule.room_name.clone(), owner_id);
+        room.scheduled_for = Some(run_time);
+        rooms.insert(room.id, room);
+    }
+}
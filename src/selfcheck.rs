@@ -0,0 +1,62 @@
+//! Backs the `--check` CLI flag: validates config and connects to the
+//! configured sqlite database and Redis server, so a
+//! deployment can fail fast before replacing a working instance instead
+//! of discovering a bad config only once traffic starts arriving.
+
+use tracing::{error, info};
+
+use crate::config::LoadReport;
+
+/// Runs every check this build knows how to run, logging each outcome as
+/// it goes, and returns whether they all passed.
+pub async fn run(report: &LoadReport) -> bool {
+    let mut ok = true;
+
+    for issue in &report.issues {
+        error!(field = %issue.field, "config: {}", issue.message);
+        ok = false;
+    }
+    if report.issues.is_empty() {
+        info!("config: ok");
+    }
+
+    if let Some(path) = &report.config.sqlite_path {
+        match crate::persistence::Store::open(path) {
+            Ok(_) => info!(path, "sqlite: ok"),
+            Err(err) => {
+                error!(path, %err, "sqlite: failed to open");
+                ok = false;
+            }
+        }
+    }
+
+    if let Some(redis_url) = &report.config.redis_url {
+        ok &= check_redis(redis_url).await;
+    }
+
+    // Only config, sqlite and Redis are checked. The configured Jira and
+    // GitHub importers and the Slack/webhook notifiers (see `notify.rs`)
+    // are not contacted; this build has no SMTP or TLS integration.
+
+    ok
+}
+
+async fn check_redis(redis_url: &str) -> bool {
+    let client = match redis::Client::open(redis_url) {
+        Ok(client) => client,
+        Err(err) => {
+            error!(redis_url, %err, "redis: invalid url");
+            return false;
+        }
+    };
+    match client.get_multiplexed_async_connection().await {
+        Ok(_) => {
+            info!(redis_url, "redis: ok");
+            true
+        }
+        Err(err) => {
+            error!(redis_url, %err, "redis: failed to connect");
+            false
+        }
+    }
+}
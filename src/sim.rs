@@ -0,0 +1,111 @@
+//! Deterministic in-memory simulation harness for `Room`, driven by a
+//! seeded PRNG so a failing run is exactly reproducible from its seed.
+//! Invoked via the `--simulate[=N]` CLI flag rather than `cargo test`,
+//! since this project has no test suite to hook a harness into — see
+//! `selfcheck` for the analogous `--check` tool.
+//!
+//! Each run drives a fresh `Room` through a sequence of randomized
+//! actions (join, vote, reveal, reset, role switch, disconnect) calling
+//! the same `Room`/`Story` methods the WebSocket handler does, and
+//! checks after every action that votes stay hidden until revealed and
+//! that a revealed story always has votes to show.
+
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use uuid::Uuid;
+
+use crate::room::{Role, Room, Story, StoryPhase, User, Vote};
+
+const DEFAULT_ITERATIONS: u64 = 2000;
+const ACTIONS_PER_RUN: usize = 50;
+
+/// Runs `iterations` randomized action sequences, each seeded by its own
+/// index, and logs every invariant violation found. Returns whether all
+/// of them passed.
+pub fn run(iterations: u64) -> bool {
+    let iterations = if iterations == 0 { DEFAULT_ITERATIONS } else { iterations };
+    let mut ok = true;
+    for seed in 0..iterations {
+        if let Err(failure) = run_one(seed) {
+            tracing::error!(seed, "simulation: {failure}");
+            ok = false;
+        }
+    }
+    if ok {
+        tracing::info!(iterations, "simulation: all invariants held");
+    }
+    ok
+}
+
+fn new_user(name: String, role: Role) -> User {
+    let now = Instant::now();
+    User { id: Uuid::new_v4(), name, role, last_seen: now, joined_at: now, guest_locked: false, is_bot: false }
+}
+
+fn run_one(seed: u64) -> Result<(), String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let owner = new_user("owner".to_string(), Role::Voter);
+    let owner_id = owner.id;
+    let mut room = Room::new("sim".to_string(), owner_id);
+    let mut user_ids = vec![owner_id];
+    room.users.insert(owner_id, owner);
+    room.stories.push(Story::new("story-0".to_string(), String::new()));
+    room.current_story = Some(0);
+
+    for step in 0..ACTIONS_PER_RUN {
+        match rng.gen_range(0..6) {
+            0 => {
+                let role = if rng.gen_bool(0.2) { Role::Watcher } else { Role::Voter };
+                let user = new_user(format!("user-{step}"), role);
+                user_ids.push(user.id);
+                room.users.insert(user.id, user);
+            }
+            1 => {
+                if let (Some(&user_id), Some(story)) = (user_ids.choose(&mut rng), room.current_story.and_then(|i| room.stories.get_mut(i))) {
+                    if !story.revealed && story.is_eligible_voter(user_id) {
+                        story.votes.insert(user_id, Vote { value: "3".to_string(), voted_at: chrono::Utc::now(), late: false });
+                    }
+                }
+            }
+            2 => {
+                if let Some(story) = room.current_story.and_then(|i| room.stories.get_mut(i)) {
+                    story.revealed = true;
+                    story.phase = StoryPhase::Revealed;
+                }
+            }
+            3 => {
+                if let Some(story) = room.current_story.and_then(|i| room.stories.get_mut(i)) {
+                    story.revealed = false;
+                    story.votes.clear();
+                    story.phase = StoryPhase::Voting;
+                }
+            }
+            4 => {
+                if let Some(user) = user_ids.choose(&mut rng).and_then(|id| room.users.get_mut(id)) {
+                    user.role = if rng.gen_bool(0.5) { Role::Voter } else { Role::Watcher };
+                }
+            }
+            _ => {
+                if user_ids.len() > 1 {
+                    let idx = rng.gen_range(1..user_ids.len());
+                    let id = user_ids.remove(idx);
+                    room.users.remove(&id);
+                }
+            }
+        }
+
+        if let Some(story) = room.current_story.and_then(|i| room.stories.get(i)) {
+            let view = story.view(&room.config, &room.users);
+            if !story.revealed && view.votes.is_some() {
+                return Err(format!("seed {seed} step {step}: hidden-vote leak — votes visible before reveal"));
+            }
+            if story.revealed && !story.votes.is_empty() && view.votes.is_none() {
+                return Err(format!("seed {seed} step {step}: revealed story has votes cast but none visible"));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::room::{Room, RoomConfig, Story};
+
+/// A fully self-contained, serializable copy of a room's state. Used for
+/// owner-triggered backups, room-from-snapshot creation, and (later)
+/// bulk export/import.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomSnapshot {
+    pub name: String,
+    pub config: RoomConfig,
+    pub stories: Vec<StorySnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorySnapshot {
+    pub title: String,
+    pub description: String,
+    pub final_estimate: Option<String>,
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    #[serde(default)]
+    pub checklist: HashMap<String, bool>,
+    #[serde(default)]
+    pub story_url: Option<String>,
+    #[serde(default)]
+    pub issue_key: Option<String>,
+}
+
+impl From<&Story> for StorySnapshot {
+    fn from(story: &Story) -> Self {
+        Self {
+            title: story.title.clone(),
+            description: story.description.clone(),
+            final_estimate: story.final_estimate.clone(),
+            attachments: story.attachments.clone(),
+            checklist: story.checklist.clone(),
+            story_url: story.story_url.clone(),
+            issue_key: story.issue_key.clone(),
+        }
+    }
+}
+
+impl Room {
+    /// Snapshots stories only by their durable fields (title, description,
+    /// final estimate, attachments, checklist, story URL and issue key) —
+    /// in-progress votes and connected users are
+    /// session-local and deliberately left out of exports.
+    pub fn to_snapshot(&self) -> RoomSnapshot {
+        RoomSnapshot {
+            name: self.name.clone(),
+            config: self.config.clone(),
+            stories: self.stories.iter().map(StorySnapshot::from).collect(),
+        }
+    }
+
+    /// Builds a brand-new room from a snapshot, owned by the caller's
+    /// `owner_id`, with a fresh room id and per-story ids so imports never
+    /// collide with whatever they were exported from.
+    pub fn from_snapshot(snapshot: RoomSnapshot, owner_id: Uuid) -> Self {
+        let mut room = Room::new(snapshot.name, owner_id);
+        room.config = snapshot.config;
+        room.stories = snapshot
+            .stories
+            .into_iter()
+            .map(|s| {
+                let mut story = Story::new(s.title, s.description);
+                story.final_estimate = s.final_estimate;
+                story.attachments = s.attachments;
+                story.checklist = s.checklist;
+                story.story_url = s.story_url;
+                story.issue_key = s.issue_key;
+                story
+            })
+            .collect();
+        room
+    }
+}
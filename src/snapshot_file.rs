@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::persistence::PersistedRoom;
+use crate::state::AppState;
+
+/// Lighter-weight alternative to the SQLite backend (`persistence.rs`):
+/// periodically dumps every room to a single JSON file and reloads it on
+/// startup, so a crash or redeploy doesn't wipe an active session even
+/// without an embedded database.
+pub async fn run(state: AppState, path: String, interval: Duration) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        let persisted: Vec<PersistedRoom> = {
+            let rooms = state.rooms.read().await;
+            rooms.values().map(PersistedRoom::from).collect()
+        };
+        match serde_json::to_string(&persisted) {
+            Ok(data) => {
+                if let Err(err) = std::fs::write(&path, data) {
+                    warn!(%err, path, "failed to write periodic room snapshot");
+                }
+            }
+            Err(err) => warn!(%err, "failed to serialize rooms for periodic snapshot"),
+        }
+    }
+}
+
+/// Loads whatever was written by `run` into `state`. Called once at
+/// startup, before the server starts accepting connections. A missing or
+/// unreadable file just means there's nothing to restore.
+pub async fn restore(state: &AppState, path: &str) {
+    let Ok(data) = std::fs::read_to_string(path) else { return };
+    let Ok(rooms) = serde_json::from_str::<Vec<PersistedRoom>>(&data) else {
+        warn!(path, "snapshot file exists but could not be parsed, starting empty");
+        return;
+    };
+    if rooms.is_empty() {
+        return;
+    }
+    let mut guard = state.rooms.write().await;
+    for room in rooms {
+        guard.insert(room.id, room.into_room());
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::cluster::ClusterDirectory;
+use crate::config::Config;
+use crate::notify::NotificationQueue;
+use crate::persistence::Store;
+use crate::room::Room;
+use crate::schedule::RecurringSchedule;
+use crate::snapshot::RoomSnapshot;
+
+/// Shared, process-wide application state. Cloned cheaply into every
+/// axum handler via `Extension`/`State`.
+#[derive(Clone)]
+pub struct AppState {
+    pub rooms: Arc<RwLock<HashMap<Uuid, Room>>>,
+    pub schedules: Arc<RwLock<Vec<RecurringSchedule>>>,
+    /// Ad-hoc templates saved from an existing room's state, keyed by
+    /// template id, that new rooms can be instantiated from.
+    pub templates: Arc<RwLock<HashMap<Uuid, RoomSnapshot>>>,
+    /// Outbound notification fan-out (Slack, webhooks, ...). Starts with
+    /// no registered destinations until one is configured.
+    pub notifications: Arc<NotificationQueue>,
+    /// The SQLite persistence backend, if `sqlite_path` was configured.
+    /// `None` disables any endpoint that needs durable storage beyond a
+    /// room's own in-memory lifetime.
+    pub persistence: Option<Arc<Store>>,
+    /// The multi-instance room directory, if `redis_url` was configured.
+    pub cluster: Option<Arc<ClusterDirectory>>,
+    /// The loaded server config, kept around for handlers (e.g. the
+    /// metrics exporter) that need to read a setting at request time
+    /// rather than just at startup.
+    pub config: Arc<Config>,
+    /// Count of client frames that arrived in the legacy `"<type> <json>"`
+    /// framing (see `handler::parse_legacy_message`) rather than the
+    /// current typed envelope, so operators can tell when every client
+    /// has migrated and the bridge can be dropped.
+    pub legacy_protocol_messages: Arc<AtomicU64>,
+}
+
+impl AppState {
+    pub fn new() -> Self {
+        Self {
+            rooms: Arc::new(RwLock::new(HashMap::new())),
+            schedules: Arc::new(RwLock::new(Vec::new())),
+            templates: Arc::new(RwLock::new(HashMap::new())),
+            notifications: Arc::new(NotificationQueue::start(Vec::new())),
+            persistence: None,
+            cluster: None,
+            config: Arc::new(Config::default()),
+            legacy_protocol_messages: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::room::{RoomConfig, Story, User};
+
+/// Summary statistics over a story's numeric votes. Non-numeric card
+/// values (e.g. "?" or T-shirt sizes) are simply excluded rather than
+/// causing the whole calculation to fail.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoteStats {
+    pub numeric_vote_count: usize,
+    pub average: Option<f64>,
+    /// Average weighted by each voter's `RoomConfig::role_weights`
+    /// entry, so e.g. a tech lead's vote can count for more without
+    /// hiding anyone else's vote from the raw `average`.
+    pub weighted_average: Option<f64>,
+    pub median: Option<f64>,
+    /// The most frequently cast numeric value. `None` if every value
+    /// occurs exactly once (no value is more "mode" than any other).
+    pub mode: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Population standard deviation of the numeric votes (the story's
+    /// cast votes are the whole population being summarized, not a
+    /// sample of some larger one).
+    pub std_dev: Option<f64>,
+    /// True when every numeric vote landed on the same deck step, or
+    /// adjacent ones — close enough that the team can settle without
+    /// another round of discussion.
+    pub consensus: bool,
+    /// The deck card closest to `average`, for a facilitator who wants a
+    /// one-click "go with this" instead of eyeballing the numbers.
+    pub suggested_estimate: Option<String>,
+}
+
+/// Locale prefixes (the language subtag of a BCP-47 tag) that write
+/// numbers with a comma decimal separator and a period (or space) for
+/// thousands, as opposed to the server's plain `.`-decimal default.
+const COMMA_DECIMAL_LANGUAGES: &[&str] = &["de", "fr", "it", "es", "nl", "pt", "ru", "pl", "sv", "da", "fi"];
+
+/// Formats a number for `RoomConfig::locale`, used by server-generated
+/// exports and notifications so a number like `8.5` reads as `8,5` for
+/// teams that set a European locale. Falls back to plain `.`-decimal
+/// formatting when `locale` is `None` or unrecognized — this is a small
+/// decimal-separator hint, not a full i18n number formatter.
+pub fn format_number_for_locale(value: f64, locale: Option<&str>) -> String {
+    let formatted = format!("{value}");
+    let language = locale.and_then(|tag| tag.split(['-', '_']).next()).map(str::to_ascii_lowercase);
+    match language {
+        Some(language) if COMMA_DECIMAL_LANGUAGES.contains(&language.as_str()) => formatted.replace('.', ","),
+        _ => formatted,
+    }
+}
+
+/// A gentle, non-blocking heads-up about a pathological voting pattern
+/// seen across the whole session, surfaced to facilitators alongside
+/// results rather than acted on by the server in any way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionAdvisory {
+    /// Every revealed story with more than one vote converged on the
+    /// exact same card, which can mean voters are anchoring on each
+    /// other (or on the facilitator) instead of estimating independently.
+    NoVoteVariance,
+    /// Revealed stories routinely span the entire deck (min and max
+    /// votes hit the deck's extremes), which can mean the deck's
+    /// granularity doesn't match the team's actual uncertainty, or
+    /// stories aren't being broken down small enough to agree on.
+    FullDeckSpread,
+}
+
+/// Need at least this many revealed, multi-vote stories before forming a
+/// judgement — one or two stories isn't a pattern.
+const MIN_STORIES_FOR_ADVISORY: usize = 3;
+/// Fraction of revealed stories that must hit both deck extremes before
+/// flagging `SessionAdvisory::FullDeckSpread`.
+const FULL_DECK_SPREAD_THRESHOLD: f64 = 0.6;
+
+/// Scans every revealed story in the room for the patterns described by
+/// `SessionAdvisory`. Called after each reveal; returns an empty list
+/// until there's been enough of the session to judge.
+pub fn session_advisories(stories: &[Story], deck: &[String]) -> Vec<SessionAdvisory> {
+    let revealed: Vec<&Story> = stories.iter().filter(|s| s.revealed && s.votes.len() > 1).collect();
+    if revealed.len() < MIN_STORIES_FOR_ADVISORY {
+        return Vec::new();
+    }
+
+    let mut advisories = Vec::new();
+
+    let unanimous_count = revealed
+        .iter()
+        .filter(|s| {
+            let mut values = s.votes.values().map(|v| v.value.as_str());
+            let Some(first) = values.next() else { return false };
+            values.all(|v| v == first)
+        })
+        .count();
+    if unanimous_count == revealed.len() {
+        advisories.push(SessionAdvisory::NoVoteVariance);
+    }
+
+    let numeric_deck: Vec<f64> = deck.iter().filter_map(|c| c.parse().ok()).filter(|v: &f64| v.is_finite()).collect();
+    if numeric_deck.len() >= 2 {
+        let deck_min = numeric_deck.iter().cloned().fold(f64::INFINITY, f64::min);
+        let deck_max = numeric_deck.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let full_spread_count = revealed
+            .iter()
+            .filter(|s| {
+                let numeric: Vec<f64> = s.votes.values().filter_map(|v| v.value.parse().ok()).collect();
+                if numeric.len() < 2 {
+                    return false;
+                }
+                let min = numeric.iter().cloned().fold(f64::INFINITY, f64::min);
+                let max = numeric.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                min <= deck_min && max >= deck_max
+            })
+            .count();
+        if full_spread_count as f64 / revealed.len() as f64 >= FULL_DECK_SPREAD_THRESHOLD {
+            advisories.push(SessionAdvisory::FullDeckSpread);
+        }
+    }
+
+    advisories
+}
+
+fn role_weight(config: &RoomConfig, user: Option<&User>) -> f64 {
+    let Some(user) = user else { return 1.0 };
+    config.role_weights.get(&user.role).copied().unwrap_or(1.0)
+}
+
+/// Computes vote statistics for a story, weighting each numeric vote by
+/// the voter's role (see `RoomConfig::role_weights`). Votes from users
+/// outside the story's `Story::voter_scope`, if narrowed, are excluded.
+pub fn compute(story: &Story, config: &RoomConfig, users: &HashMap<Uuid, User>) -> VoteStats {
+    let eligible_votes: Vec<&str> =
+        story.votes.iter().filter(|(user_id, _)| story.is_eligible_voter(**user_id)).map(|(_, vote)| vote.value.as_str()).collect();
+
+    let numeric: Vec<(f64, f64)> = story
+        .votes
+        .iter()
+        .filter(|(user_id, _)| story.is_eligible_voter(**user_id))
+        .filter_map(|(user_id, vote)| {
+            let value: f64 = vote.value.parse().ok()?;
+            value.is_finite().then_some((value, role_weight(config, users.get(user_id))))
+        })
+        .collect();
+
+    if numeric.is_empty() {
+        // Either nobody's voted yet, or the deck is non-numeric (e.g.
+        // T-shirt sizes) — consensus/suggestion still make sense there,
+        // just going by the literal card label instead of a number.
+        return VoteStats {
+            numeric_vote_count: 0,
+            average: None,
+            weighted_average: None,
+            median: None,
+            mode: None,
+            min: None,
+            max: None,
+            std_dev: None,
+            consensus: raw_consensus(&eligible_votes),
+            suggested_estimate: raw_mode(&eligible_votes),
+        };
+    }
+
+    let sum: f64 = numeric.iter().map(|(v, _)| v).sum();
+    let weight_sum: f64 = numeric.iter().map(|(_, w)| w).sum();
+    let weighted_sum: f64 = numeric.iter().map(|(v, w)| v * w).sum();
+    let min = numeric.iter().map(|(v, _)| *v).fold(f64::INFINITY, f64::min);
+    let max = numeric.iter().map(|(v, _)| *v).fold(f64::NEG_INFINITY, f64::max);
+    let average = sum / numeric.len() as f64;
+
+    let mut sorted: Vec<f64> = numeric.iter().map(|(v, _)| *v).collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mid = sorted.len() / 2;
+    let median = if sorted.len() % 2 == 0 { (sorted[mid - 1] + sorted[mid]) / 2.0 } else { sorted[mid] };
+
+    let mode = mode_of(&sorted);
+
+    let variance = numeric.iter().map(|(v, _)| (v - average).powi(2)).sum::<f64>() / numeric.len() as f64;
+
+    let numeric_deck: Vec<f64> = config.deck.iter().filter_map(|c| c.parse().ok()).filter(|v: &f64| v.is_finite()).collect();
+    let (consensus, suggested_estimate) = consensus_and_suggestion(min, max, average, &numeric_deck, &config.deck);
+
+    VoteStats {
+        numeric_vote_count: numeric.len(),
+        average: Some(average),
+        weighted_average: if weight_sum > 0.0 { Some(weighted_sum / weight_sum) } else { None },
+        median: Some(median),
+        mode,
+        min: Some(min),
+        max: Some(max),
+        std_dev: Some(variance.sqrt()),
+        consensus,
+        suggested_estimate,
+    }
+}
+
+/// Whether `min` and `max` sit on the same or adjacent deck steps (by
+/// position in the deck sorted ascending, not raw value — decks aren't
+/// always linear), plus the deck card nearest `average`. Falls back to
+/// `min == max` and no suggestion when the deck has fewer than two
+/// numeric cards to measure steps against.
+fn consensus_and_suggestion(min: f64, max: f64, average: f64, numeric_deck: &[f64], deck: &[String]) -> (bool, Option<String>) {
+    if numeric_deck.len() < 2 {
+        return (min == max, None);
+    }
+    let mut sorted_deck = numeric_deck.to_vec();
+    sorted_deck.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let nearest_index = |value: f64| {
+        sorted_deck
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (*a - value).abs().partial_cmp(&(*b - value).abs()).unwrap())
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    };
+    let consensus = nearest_index(max).abs_diff(nearest_index(min)) <= 1;
+
+    let nearest_value = sorted_deck[nearest_index(average)];
+    let suggested_estimate = deck.iter().find(|card| card.parse::<f64>().ok() == Some(nearest_value)).cloned();
+
+    (consensus, suggested_estimate)
+}
+
+/// The most frequent value in a sorted slice, or `None` if no value
+/// occurs more than once. Ties favor the smallest value.
+fn mode_of(sorted: &[f64]) -> Option<f64> {
+    let mut best_value = sorted[0];
+    let mut best_count = 0usize;
+    let mut run_value = sorted[0];
+    let mut run_count = 0usize;
+    for &value in sorted {
+        if value == run_value {
+            run_count += 1;
+        } else {
+            run_value = value;
+            run_count = 1;
+        }
+        if run_count > best_count {
+            best_count = run_count;
+            best_value = run_value;
+        }
+    }
+    (best_count > 1).then_some(best_value)
+}
+
+/// Same idea as `consensus_and_suggestion`, but for decks with no (or too
+/// few) numeric cards — T-shirt sizes and the like — where "nearest deck
+/// step" doesn't mean anything and literal card equality is all there is
+/// to go on.
+fn raw_consensus(votes: &[&str]) -> bool {
+    match votes.split_first() {
+        Some((first, rest)) => rest.iter().all(|v| v == first),
+        None => false,
+    }
+}
+
+/// The most commonly cast card label, or `None` if there are no votes or
+/// no label is cast more than once. Ties favor whichever card sorts
+/// first, for a deterministic result.
+fn raw_mode(votes: &[&str]) -> Option<String> {
+    if votes.is_empty() {
+        return None;
+    }
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for &value in votes {
+        *counts.entry(value).or_insert(0) += 1;
+    }
+    let max_count = *counts.values().max()?;
+    if max_count <= 1 {
+        return None;
+    }
+    counts.into_iter().filter(|(_, count)| *count == max_count).map(|(value, _)| value.to_string()).min()
+}
@@ -0,0 +1,515 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::room::{ApiTokenScope, LateVotePolicy, Role, RoomConfig, StoryPhase, StoryView, User, Vote};
+use crate::snapshot::RoomSnapshot;
+
+/// The protocol version this build speaks. Bump when a message shape
+/// changes in a way an old client can't just ignore (new required field,
+/// removed variant); additive changes (new optional field, new variant a
+/// client can ignore) don't need a bump.
+pub const CURRENT_PROTOCOL_VERSION: u32 = 1;
+
+// Every WebSocket frame in both directions is a single JSON envelope of
+// the form `{"type": "vote", ...payload fields inline}`, produced by
+// serde's internally-tagged representation on `ClientMessage` and
+// `ServerMessage` below; adding a message is just adding an enum variant.
+// Incoming frames in the older `"<prefix> <json>"` framing are still
+// accepted and bridged onto `ClientMessage` by
+// `handler::parse_legacy_message`.
+
+/// A `ClientMessage` plus an optional client-chosen correlation id. Kept
+/// as a separate wrapper (via `#[serde(flatten)]`) rather than adding
+/// `request_id` to every `ClientMessage` variant, so the ack/nack concern
+/// doesn't leak into match arms that don't care about it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncomingMessage {
+    #[serde(default)]
+    pub request_id: Option<String>,
+    #[serde(flatten)]
+    pub message: ClientMessage,
+}
+
+/// Messages sent by a connected client over the room WebSocket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Join {
+        name: String,
+        role: Role,
+        /// When this matches `Room::watcher_guest_token`, the join is
+        /// forced into `Watcher` regardless of `role` and locked there.
+        #[serde(default)]
+        guest_token: Option<String>,
+        /// Set by automation (importer bots, dashboards) identifying
+        /// itself as non-human. See `User::is_bot`.
+        #[serde(default)]
+        is_bot: bool,
+        /// The `owner_id` minted and returned once by `POST /api/rooms`
+        /// (or `create_room_from_template`). When this matches
+        /// `Room::owner_id`, the connecting client claims the owner seat
+        /// (id and `Role::Owner`) instead of being assigned a random id
+        /// — otherwise nothing could ever connect as the id every
+        /// owner-only command checks against.
+        #[serde(default)]
+        owner_token: Option<Uuid>,
+    },
+    Vote { story_id: Uuid, value: String },
+    Reveal,
+    Reset,
+    AddStory { title: String, description: String },
+    SelectStory { story_id: Uuid },
+    /// Owner-only: reorders the backlog to exactly `story_ids`, which
+    /// must be a permutation of the room's current story ids — rejected
+    /// otherwise rather than silently dropping or appending stories.
+    ReorderStories { story_ids: Vec<Uuid> },
+    /// Owner-only: marks a story out of scope instead of voting on it.
+    /// See `StoryPhase::Skipped`.
+    Skip { story_id: Uuid },
+    /// Explicitly leaves the room instead of just dropping the socket.
+    /// Either way the participant is removed with the same `UsersDelta`,
+    /// so other clients see no difference between the two.
+    Leave,
+    /// Resets the idle clock without otherwise changing room state, so a
+    /// long-lived but quiet tab can keep its room from expiring.
+    KeepAlive,
+    /// Owner-only: exempt (or un-exempt) this room from the idle GC.
+    SetPersistent { persistent: bool },
+    /// Owner-only: ask the server to send back a full snapshot of the
+    /// room for the client to offer as a downloadable backup.
+    ExportSnapshot,
+    /// Owner-only: change another participant's role.
+    ChangeRole { user_id: Uuid, role: Role },
+    /// Owner-only: remove a participant who joined by mistake (or whose
+    /// missing vote is blocking auto-reveal) and force-disconnect their
+    /// connection.
+    Kick { user_id: Uuid },
+    /// Owner-only: like `Kick`, but also bans the participant's name from
+    /// rejoining this room for its lifetime (see `Room::banned_names`).
+    Ban { user_id: Uuid },
+    /// Owner-only: reverses a previous `Ban` for the given name.
+    Unban { name: String },
+    /// Owner-only: hands off ownership (and everything owner-only that
+    /// goes with it — kick/ban/deck/settings/etc.) to another participant, so
+    /// a facilitator who has to leave mid-session doesn't strand the
+    /// room without anyone able to run it.
+    TransferOwnership { user_id: Uuid },
+    /// Owner-only: save the room's current config and stories as a
+    /// reusable template new rooms can be created from.
+    SaveAsTemplate,
+    /// Owner-only: spin up a fresh room carrying over this room's settings
+    /// and any stories that were never finished (no `final_estimate` yet),
+    /// so a session cut short can resume next day with a clean attendee
+    /// list instead of everyone re-joining the old one.
+    CloneRoom,
+    /// Owner-only: toggle whole-room break/coffee mode.
+    SetBreakMode { on_break: bool },
+    /// Owner-only: rename the room (e.g. "Team Rocket Sprint 42") so the
+    /// UI and any exports/admin listings can show something better than
+    /// the room's UUID.
+    SetRoomName { name: String },
+    /// Owner-only: set (or clear) a voting deadline and late-vote policy
+    /// for a story.
+    SetStoryDeadline {
+        story_id: Uuid,
+        deadline: Option<DateTime<Utc>>,
+        late_vote_policy: LateVotePolicy,
+    },
+    /// Owner-only: swap the room's card deck mid-session. Existing votes
+    /// are migrated to the closest numeric value in the new deck rather
+    /// than being discarded.
+    SetDeck { deck: Vec<String> },
+    /// Owner-only: replace a story's attachment links (mockups,
+    /// screenshots, design docs) wholesale.
+    SetAttachments { story_id: Uuid, attachments: Vec<String> },
+    /// Owner-only: ask the server for a shareable link that forces
+    /// whoever opens it into a locked `Watcher` role.
+    RequestGuestLink,
+    /// Owner-only: ask for a read-only link (see `Room::spectator_token`)
+    /// that connects to `handler::observe_route` and receives every
+    /// broadcast — for stakeholders who should watch live without ever
+    /// appearing in the user list or being able to vote, add stories, or
+    /// send any other state-changing message.
+    RequestSpectatorLink,
+    /// Owner-only: fetch issues from the configured JIRA instance (see
+    /// `Config::jira`) and append them as stories. `query` is either a
+    /// JQL expression or a comma-separated list of issue keys.
+    ImportJira { query: String },
+    /// Owner-only: fetch open issues from a GitHub repo (see
+    /// `Config::github`), optionally filtered by label and/or milestone,
+    /// and append them as stories.
+    ImportGithub {
+        repo: String,
+        #[serde(default)]
+        label: Option<String>,
+        #[serde(default)]
+        milestone: Option<String>,
+    },
+    /// Owner-only: mint a new scoped API token for room automations.
+    MintApiToken { scopes: Vec<ApiTokenScope> },
+    /// Owner-only: revoke a previously minted API token.
+    RevokeApiToken { token: String },
+    /// Owner-only: replace the room's definition-of-ready checklist
+    /// wholesale. Existing per-story tick state for items that are still
+    /// present is left alone; items removed here just stop showing up.
+    SetChecklistItems { items: Vec<String> },
+    /// Owner-only: tick (or untick) a single checklist item on a story.
+    SetChecklistItem { story_id: Uuid, item: String, checked: bool },
+    /// Owner-only: set (or clear) a story's external tracker link. The
+    /// server re-derives `Story::issue_key` from it for known trackers.
+    SetStoryUrl { story_id: Uuid, story_url: Option<String> },
+    /// Owner-only: fixes a typo in a story's URL and/or description in
+    /// place, without the vote-clearing round trip of removing and
+    /// re-adding it. Each field is left untouched when `None`.
+    EditStory {
+        story_id: Uuid,
+        #[serde(default)]
+        story_url: Option<String>,
+        #[serde(default)]
+        description: Option<String>,
+    },
+    /// Owner-only: narrow (or, with `None`, widen back to everyone) which
+    /// voters a story applies to. See `Story::voter_scope`.
+    SetStoryVoterScope { story_id: Uuid, voter_ids: Option<Vec<Uuid>> },
+    /// Owner-only: clears a story's votes and un-reveals it, whether or
+    /// not it's the current story, so a split or mistaken result can be
+    /// re-estimated from scratch. Unlike `Reset`, which only affects the
+    /// current story and isn't owner-restricted, this targets any story
+    /// by id.
+    Revote { story_id: Uuid },
+    /// Owner-only: records the value the team actually agreed on after
+    /// discussion, which may differ from any individual vote. Surfaced
+    /// in `StoryView::final_estimate` for exports and the UI.
+    SetFinalEstimate { story_id: Uuid, value: Option<String> },
+    /// Owner-only: moves a story to a new step in the facilitation flow
+    /// (see `StoryPhase`) and optionally starts a countdown for it.
+    /// Setting `phase` to `Revealed` also sets `Story::revealed`, same as
+    /// `Reveal`; moving away from it un-reveals, same as `Reset`.
+    SetStoryPhase { story_id: Uuid, phase: StoryPhase, timer_secs: Option<u64> },
+    /// Owner-only: starts a countdown on a story, ticking once per second
+    /// (see `ServerMessage::TimerTick`) and auto-revealing it when it
+    /// hits zero, unless it's revealed manually first.
+    StartTimer { story_id: Uuid, seconds: u64 },
+    /// Owner-only: switches the room's deck to one of the server's named
+    /// presets (see `Config::deck_presets`), the same way `SetDeck` does
+    /// for an ad-hoc one — existing votes are migrated, not discarded.
+    SelectDeckPreset { name: String },
+    /// Owner-only: applies one or more `RoomConfig` changes at once, for
+    /// a settings panel that wants to save several toggles together
+    /// instead of firing off a separate command per field. Any field left
+    /// `None` is left at its current value.
+    UpdateRoomSettings(RoomSettingsPatch),
+}
+
+/// See `ClientMessage::UpdateRoomSettings`. Mirrors a subset of
+/// `RoomConfig` field-for-field; `deck` migrates existing votes the same
+/// way `ClientMessage::SetDeck` does.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoomSettingsPatch {
+    #[serde(default)]
+    pub deck: Option<Vec<String>>,
+    #[serde(default)]
+    pub hide_watchers: Option<bool>,
+    #[serde(default)]
+    pub auto_reveal_delay_secs: Option<u64>,
+    #[serde(default)]
+    pub owner_sees_live_votes: Option<bool>,
+    #[serde(default)]
+    pub hide_owner: Option<bool>,
+    #[serde(default)]
+    pub allow_vote_change_after_reveal: Option<bool>,
+    #[serde(default)]
+    pub anonymous_reveal: Option<bool>,
+    #[serde(default)]
+    pub jira_writeback: Option<bool>,
+    #[serde(default)]
+    pub slack_webhook_url: Option<String>,
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+/// Why the server is closing a WebSocket connection, mapped to a
+/// meaningful close code/reason so clients can show the right message
+/// and decide whether to auto-reconnect.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    Kicked,
+    Banned,
+    RoomClosed,
+    ServerShutdown,
+    IdleTimeout,
+    ProtocolError,
+}
+
+impl CloseReason {
+    /// WS close code. Reuses the two relevant IANA-registered codes
+    /// (`1002` protocol error, `1012` service restart) and otherwise
+    /// picks from the private-use range since there's no standard code
+    /// for "kicked" or "idle timeout".
+    pub fn code(self) -> u16 {
+        match self {
+            CloseReason::Kicked => 4001,
+            CloseReason::Banned => 4002,
+            CloseReason::RoomClosed => 4003,
+            CloseReason::ServerShutdown => 1012,
+            CloseReason::IdleTimeout => 4004,
+            CloseReason::ProtocolError => 1002,
+        }
+    }
+
+    pub fn reason_text(self) -> &'static str {
+        match self {
+            CloseReason::Kicked => "kicked by the room owner",
+            CloseReason::Banned => "banned from this room",
+            CloseReason::RoomClosed => "room closed",
+            CloseReason::ServerShutdown => "server shutting down",
+            CloseReason::IdleTimeout => "idle timeout",
+            CloseReason::ProtocolError => "protocol error",
+        }
+    }
+}
+
+/// Suggested reconnect backoff parameters for clients, carried in every
+/// `RoomConfigMessage`. Clients should pick a random delay in
+/// `[min_backoff_ms, max_backoff_ms]`, growing with each consecutive
+/// failure, plus up to `jitter_ms` of extra randomness so a fleet of
+/// clients that all lost their connection at once don't retry in sync.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ReconnectPolicy {
+    pub min_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub jitter_ms: u64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy { min_backoff_ms: 500, max_backoff_ms: 30_000, jitter_ms: 1_000 }
+    }
+}
+
+/// Machine-readable category for `ServerMessage::Error`, so a client can
+/// branch on behavior (e.g. re-show a login prompt on `Forbidden`)
+/// without string-matching `message`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The actor isn't allowed to do this (usually: not the room owner).
+    Forbidden,
+    /// The room (or whatever else was looked up) doesn't exist.
+    NotFound,
+    /// The request couldn't even be parsed as a known client message, or
+    /// its arguments are malformed (e.g. `ReorderStories` ids that aren't
+    /// a permutation of the room's stories).
+    InvalidMessage,
+    /// The action is understood but rejected by current room/story state
+    /// (e.g. voting after the deadline, or after the memory budget is
+    /// exhausted).
+    Rejected,
+}
+
+/// Hints for clients to play a sound or fire a desktop notification.
+/// Kept separate from the state-carrying messages below so the UI layer
+/// can map each hint to a sound/notification independent of what
+/// triggered it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationHint {
+    Revealed,
+    AllVoted,
+}
+
+/// Messages pushed from the server to one or all clients in a room.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Sent once, immediately after the socket connects and before
+    /// anything else, naming the protocol version negotiated for this
+    /// connection — `min(what the client asked for, CURRENT_PROTOCOL_VERSION)`.
+    /// A client that asked for a version the server doesn't speak yet can
+    /// compare this against its own minimum supported version and show a
+    /// "please refresh" banner instead of silently misparsing messages.
+    ProtocolNegotiated {
+        version: u32,
+    },
+    /// Sent instead of `RoomState` and followed by the socket closing when
+    /// a room is password-protected (see `Room::password`) and the
+    /// handshake's query-param password is missing or wrong. The
+    /// connection is never added as a participant, so nothing else about
+    /// the room — not even its user list — is ever sent first. Also sent
+    /// to a `handler::observe_route` connection whose spectator token is
+    /// missing or wrong.
+    JoinRejected {
+        reason: String,
+    },
+    RoomState {
+        seq: u64,
+        users: Vec<User>,
+        stories: Vec<StoryView>,
+        current_story: Option<Uuid>,
+        on_break: bool,
+    },
+    /// Incremental alternative to `RoomState` for user-list-only changes
+    /// (join/leave/role change/idle reap), so large rooms don't resend
+    /// every story on every arrival. `seq` must follow directly from the
+    /// last `RoomState`/`UsersDelta` a client saw; a gap means it missed
+    /// one and should wait for (or request) a full `RoomState` instead
+    /// of applying the delta against stale state.
+    UsersDelta {
+        seq: u64,
+        added: Vec<User>,
+        updated: Vec<User>,
+        removed: Vec<Uuid>,
+    },
+    RoomConfigMessage {
+        name: String,
+        config: RoomConfig,
+        /// When this room will be garbage-collected if it stays idle,
+        /// so clients can warn users before state is lost.
+        expires_at: Option<DateTime<Utc>>,
+        /// Suggested backoff for the client's *own* reconnect logic, so
+        /// thousands of clients don't all retry in lockstep after a
+        /// restart.
+        reconnect: ReconnectPolicy,
+    },
+    /// Sent shortly before `expires_at` as a last chance for still-open
+    /// tabs to export the room; followed by the room actually vanishing
+    /// from the server once the idle GC sweep runs.
+    RoomExpiryWarning {
+        expires_at: DateTime<Utc>,
+    },
+    VoteUpdate {
+        story_id: Uuid,
+        voted_user_ids: Vec<Uuid>,
+    },
+    /// Live (pre-reveal) vote values for `story_id`, sent only when
+    /// `RoomConfig::owner_sees_live_votes` is on. Fanned out over the room
+    /// broadcast like `ForceDisconnect`, but every connection except the
+    /// one matching `for_user_id` (the room owner's) must ignore it
+    /// rather than forward it to its client.
+    OwnerLiveVotes {
+        for_user_id: Uuid,
+        story_id: Uuid,
+        votes: HashMap<Uuid, Vote>,
+    },
+    /// Reply to `ExportSnapshot`, sent only to the requesting owner.
+    Snapshot {
+        snapshot: RoomSnapshot,
+    },
+    /// Sent shortly before a quiet participant is auto-removed, so their
+    /// tab can prompt them ("still there?") before losing their seat.
+    InactivityReminder {
+        user_id: Uuid,
+    },
+    /// Instance-wide message from an operator (maintenance windows,
+    /// upcoming downtime, etc.), fanned out to every live room.
+    Announcement {
+        message: String,
+    },
+    /// Reply to `SaveAsTemplate`, sent only to the requesting owner.
+    TemplateSaved {
+        template_id: Uuid,
+    },
+    /// Reply to `CloneRoom`, sent only to the requesting owner.
+    RoomCloned {
+        room_id: Uuid,
+        join_code: String,
+    },
+    /// Broadcast after `ClientMessage::TransferOwnership` so every
+    /// connected client (not just the new owner) updates who gets to see
+    /// owner-only controls.
+    OwnershipTransferred {
+        owner_id: Uuid,
+    },
+    /// Broadcast after each reveal once there's been enough of the
+    /// session to judge (see `stats::session_advisories`). Only sent when
+    /// at least one pattern is detected, so `flags` is never empty.
+    SessionAdvisories {
+        flags: Vec<crate::stats::SessionAdvisory>,
+    },
+    /// Reply to `RequestGuestLink`, sent only to the requesting owner.
+    GuestLink {
+        url: String,
+    },
+    /// Reply to `RequestSpectatorLink`, sent only to the requesting owner.
+    SpectatorLink {
+        url: String,
+    },
+    /// Reply to `MintApiToken`, sent only to the requesting owner. The
+    /// token itself is never broadcast room-wide.
+    ApiTokenMinted {
+        token: String,
+        scopes: Vec<ApiTokenScope>,
+    },
+    /// Tells every connected client to reconnect at a different instance,
+    /// carrying the room's snapshot (settings and stories) along. Votes
+    /// and connected users are not part of the snapshot.
+    /// Sent just before the room is dropped from this process.
+    Migrate {
+        target_url: String,
+        snapshot: RoomSnapshot,
+    },
+    /// Sent privately to whichever connection triggered it (never
+    /// broadcast room-wide), so a rejected action only shows up in the
+    /// UI of the person who attempted it.
+    Error {
+        code: ErrorCode,
+        message: String,
+        /// The client message type that caused this error (e.g.
+        /// `"change_role"`), when known, so the UI can correlate the
+        /// failure with the action that triggered it.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        command: Option<String>,
+    },
+    /// Sent alongside (not instead of) the relevant state update, so
+    /// clients that ignore it still work correctly.
+    Notify {
+        hint: NotificationHint,
+    },
+    /// Confirms a `ClientMessage` that included a `request_id` was
+    /// applied. Sent in addition to (not instead of) whatever reply or
+    /// broadcast the command itself produces.
+    Ack {
+        request_id: String,
+    },
+    /// Confirms a `ClientMessage` that included a `request_id` was
+    /// rejected, carrying the same reason as the accompanying `Error`.
+    Nack {
+        request_id: String,
+        reason: String,
+    },
+    /// Incremental alternative to `RoomState` for a single story changing
+    /// (revealed, reset, deadline/attachments edited, or it becomes the
+    /// active story), so a backlog of 100+ stories doesn't get resent on
+    /// every vote reveal. `seq` follows the same drift-detection contract
+    /// as `UsersDelta` — a gap means fall back to a full `RoomState`.
+    StoryUpdate {
+        seq: u64,
+        story: StoryView,
+        current_story: Option<Uuid>,
+    },
+    /// Tells one specific connection to disconnect with a meaningful
+    /// close code/reason. Fanned out over the room broadcast like
+    /// everything else, but every socket except the one whose `user_id`
+    /// matches should ignore it rather than forward it to its client.
+    ForceDisconnect {
+        user_id: Uuid,
+        reason: CloseReason,
+    },
+    /// Tells every connection in the room to disconnect because the room
+    /// itself is going away (expired, deleted through the REST API, or the
+    /// server is shutting down). A handoff sends `Migrate` instead.
+    RoomClosing {
+        reason: CloseReason,
+    },
+    /// One second's tick of a `ClientMessage::StartTimer` countdown.
+    /// Purely informational — the server reveals on its own once it
+    /// reaches zero, clients just render it.
+    TimerTick {
+        story_id: Uuid,
+        seconds_remaining: u64,
+    },
+}